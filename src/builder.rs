@@ -0,0 +1,191 @@
+use crate::bulk_loading::BulkLoading;
+use crate::error::BuildError;
+#[cfg(any(feature = "geojson", feature = "shapefile"))]
+use crate::error::ReadError;
+use crate::intersections::FillRule;
+use crate::point_tree;
+use crate::point_tree::PointTree;
+use crate::polygon;
+use crate::polygon::{Geometry, MultiPolygon};
+use crate::predicates::Precision;
+use crate::tree;
+use crate::tree::Tree;
+
+// collects the options for building a search tree
+// num_edges_children is the number of edges in each leaf node and
+// num_nodes_children is the number of children of all other nodes
+// fill_rule, tolerance, and precision are stored in the tree and used
+// by the queries which check whether points are inside
+// without polygon ids the tree is smaller but cannot tell which polygon
+// contains a point or which polygon the nearest edge belongs to
+// for point trees num_edges_children is the number of points in each leaf
+// node and only the fan-outs and bulk loading are used
+//
+//     let tree = TreeBuilder::new()
+//         .num_edges_children(16)
+//         .bulk_loading(BulkLoading::Hilbert)
+//         .build(polygons)?;
+#[derive(Debug, Clone)]
+pub struct TreeBuilder {
+    pub(crate) num_edges_children: usize,
+    pub(crate) num_nodes_children: usize,
+    pub(crate) bulk_loading: BulkLoading,
+    pub(crate) fill_rule: FillRule,
+    pub(crate) tolerance: f64,
+    pub(crate) store_polygon_ids: bool,
+    pub(crate) precision: Precision,
+}
+
+impl Default for TreeBuilder {
+    fn default() -> TreeBuilder {
+        TreeBuilder {
+            num_edges_children: 4,
+            num_nodes_children: 4,
+            bulk_loading: BulkLoading::default(),
+            fill_rule: FillRule::EvenOdd,
+            tolerance: 0.0,
+            store_polygon_ids: true,
+            precision: Precision::default(),
+        }
+    }
+}
+
+impl TreeBuilder {
+    pub fn new() -> TreeBuilder {
+        TreeBuilder::default()
+    }
+
+    pub fn num_edges_children(mut self, num_edges_children: usize) -> TreeBuilder {
+        self.num_edges_children = num_edges_children;
+        self
+    }
+
+    pub fn num_nodes_children(mut self, num_nodes_children: usize) -> TreeBuilder {
+        self.num_nodes_children = num_nodes_children;
+        self
+    }
+
+    pub fn bulk_loading(mut self, bulk_loading: BulkLoading) -> TreeBuilder {
+        self.bulk_loading = bulk_loading;
+        self
+    }
+
+    pub fn fill_rule(mut self, fill_rule: FillRule) -> TreeBuilder {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    // points within this distance of an edge count as inside
+    pub fn tolerance(mut self, tolerance: f64) -> TreeBuilder {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn store_polygon_ids(mut self, store_polygon_ids: bool) -> TreeBuilder {
+        self.store_polygon_ids = store_polygon_ids;
+        self
+    }
+
+    pub fn precision(mut self, precision: Precision) -> TreeBuilder {
+        self.precision = precision;
+        self
+    }
+
+    pub fn build(&self, polygons: Vec<Vec<(f64, f64)>>) -> Result<Tree, BuildError> {
+        self.build_h(polygons.into_iter().map(polygon::pad).collect())
+    }
+
+    pub fn build_h(&self, polygons: Vec<Vec<(f64, f64, f64)>>) -> Result<Tree, BuildError> {
+        let num_polygons = polygons.len();
+        tree::build_tree(polygon::rings_from_polygons(polygons), num_polygons, self)
+    }
+
+    // open polylines, the last point is not connected with the first one
+    pub fn build_polylines(&self, polylines: Vec<Vec<(f64, f64)>>) -> Result<Tree, BuildError> {
+        self.build_with_polylines(Vec::new(), polylines)
+    }
+
+    // polygons and open polylines in one tree, the polylines get the ids
+    // which follow those of the polygons
+    pub fn build_with_polylines(
+        &self,
+        polygons: Vec<Vec<(f64, f64)>>,
+        polylines: Vec<Vec<(f64, f64)>>,
+    ) -> Result<Tree, BuildError> {
+        let num_polygons = polygons.len() + polylines.len();
+        let mut rings =
+            polygon::rings_from_polygons(polygons.into_iter().map(polygon::pad).collect());
+        rings.extend(polygon::rings_from_polylines(
+            polylines.into_iter().map(polygon::pad).collect(),
+            rings.len(),
+        ));
+        tree::build_tree(rings, num_polygons, self)
+    }
+
+    // each multipolygon may consist of several polygons with holes
+    // query results refer to the index of the multipolygon
+    pub fn build_multipolygons(
+        &self,
+        multipolygons: Vec<MultiPolygon>,
+    ) -> Result<Tree, BuildError> {
+        let num_polygons = multipolygons.len();
+        tree::build_tree(
+            polygon::rings_from_multipolygons(multipolygons),
+            num_polygons,
+            self,
+        )
+    }
+
+    // polygons, multipolygons, and polylines in one tree, each geometry gets
+    // the id of its position
+    pub fn build_geometries(&self, geometries: Vec<Geometry>) -> Result<Tree, BuildError> {
+        let num_polygons = geometries.len();
+        tree::build_tree(
+            polygon::rings_from_geometries(geometries),
+            num_polygons,
+            self,
+        )
+    }
+
+    // point tree for nearest point queries, without edges
+    pub fn build_points(&self, points: Vec<(f64, f64)>) -> Result<PointTree, BuildError> {
+        self.build_points_h(polygon::pad(points))
+    }
+
+    // h is added to the distance to each point
+    pub fn build_points_h(&self, points: Vec<(f64, f64, f64)>) -> Result<PointTree, BuildError> {
+        point_tree::build(points, self)
+    }
+
+    // every feature becomes one polygon or polyline with the position of the
+    // feature as id and its properties as payload
+    #[cfg(feature = "geojson")]
+    pub fn build_geojson(
+        &self,
+        geojson: &::geojson::GeoJson,
+    ) -> Result<Tree<::geojson::JsonObject>, ReadError> {
+        crate::geojson::build(self, geojson)
+    }
+
+    // polygon and polyline shapefiles from the contents of the .shp and .dbf
+    // files, every shape gets the id of its record and the attributes of its
+    // record as payload
+    #[cfg(feature = "shapefile")]
+    pub fn build_shapefile(
+        &self,
+        shp: &[u8],
+        dbf: &[u8],
+    ) -> Result<Tree<crate::shapefile::Attributes>, ReadError> {
+        crate::shapefile::build(self, shp, dbf)
+    }
+
+    // point shapefiles, the attributes are in the order of the point indices
+    #[cfg(feature = "shapefile")]
+    pub fn build_shapefile_points(
+        &self,
+        shp: &[u8],
+        dbf: &[u8],
+    ) -> Result<(PointTree, Vec<crate::shapefile::Attributes>), ReadError> {
+        crate::shapefile::build_points(self, shp, dbf)
+    }
+}
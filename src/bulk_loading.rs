@@ -0,0 +1,125 @@
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+use crate::tree::Node;
+
+// how nodes are ordered before they are grouped into parent nodes
+// InputOrder keeps the order in which the polygons were given
+// SortTileRecursive sorts the nodes of each level into vertical slices and
+// then each slice from bottom to top
+// Hilbert and Morton sort the leaf nodes along a space-filling curve
+// through the centers of their boxes
+#[cfg_attr(feature = "pyo3", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BulkLoading {
+    #[default]
+    InputOrder,
+    SortTileRecursive,
+    Hilbert,
+    Morton,
+}
+
+// number of cells along each axis of the grid on which the curves are computed
+const CURVE_ORDER: u32 = 16;
+
+// reorders the nodes of one level so that nodes which end up in the same parent
+// are close to each other
+// the space-filling curves only need to sort the leaves since the parents
+// inherit their order
+pub fn sort_nodes(
+    nodes: &mut [Node],
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    leaf_level: bool,
+) {
+    match bulk_loading {
+        BulkLoading::InputOrder => {}
+        BulkLoading::SortTileRecursive => sort_tile_recursive(nodes, num_nodes_children),
+        BulkLoading::Hilbert if leaf_level => sort_by_curve(nodes, hilbert_index),
+        BulkLoading::Morton if leaf_level => sort_by_curve(nodes, morton_index),
+        BulkLoading::Hilbert | BulkLoading::Morton => {}
+    }
+}
+
+fn center(node: &Node) -> (f64, f64) {
+    (0.5 * (node.xmin + node.xmax), 0.5 * (node.ymin + node.ymax))
+}
+
+// Leutenegger, Lopez, and Edgington, "STR: A Simple and Efficient Algorithm
+// for R-Tree Packing", Proceedings of the 13th International Conference on
+// Data Engineering (1997)
+fn sort_tile_recursive(nodes: &mut [Node], num_nodes_children: usize) {
+    let num_parents = nodes.len().div_ceil(num_nodes_children);
+    let num_slices = (num_parents as f64).sqrt().ceil() as usize;
+    let slice_size = num_slices * num_nodes_children;
+
+    nodes.sort_by(|a, b| center(a).0.total_cmp(&center(b).0));
+    for slice in nodes.chunks_mut(slice_size) {
+        slice.sort_by(|a, b| center(a).1.total_cmp(&center(b).1));
+    }
+}
+
+fn sort_by_curve(nodes: &mut [Node], index: fn(u32, u32) -> u64) {
+    let (mut xmin, mut xmax, mut ymin, mut ymax) = (f64::MAX, -f64::MAX, f64::MAX, -f64::MAX);
+    for node in nodes.iter() {
+        let (x, y) = center(node);
+        xmin = xmin.min(x);
+        xmax = xmax.max(x);
+        ymin = ymin.min(y);
+        ymax = ymax.max(y);
+    }
+
+    // centers are mapped onto the grid cells, a degenerate extent maps to cell 0
+    let cells = ((1_u32 << CURVE_ORDER) - 1) as f64;
+    let to_cell = |v: f64, min: f64, max: f64| {
+        if max > min {
+            ((v - min) / (max - min) * cells) as u32
+        } else {
+            0
+        }
+    };
+
+    nodes.sort_by_cached_key(|node| {
+        let (x, y) = center(node);
+        index(to_cell(x, xmin, xmax), to_cell(y, ymin, ymax))
+    });
+}
+
+// position of the cell (x, y) along the Hilbert curve
+// follows the well-known iterative "xy2d" conversion
+fn hilbert_index(x: u32, y: u32) -> u64 {
+    let (mut x, mut y) = (x as u64, y as u64);
+    let n = 1_u64 << CURVE_ORDER;
+    let mut d = 0;
+
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = u64::from(x & s > 0);
+        let ry = u64::from(y & s > 0);
+        d += s * s * ((3 * rx) ^ ry);
+
+        // rotate the quadrant so that the curve continues in the right direction
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        s /= 2;
+    }
+
+    d
+}
+
+// position of the cell (x, y) along the Morton (z-order) curve
+// which interleaves the bits of x and y
+fn morton_index(x: u32, y: u32) -> u64 {
+    let mut d = 0;
+    for bit in 0..CURVE_ORDER {
+        d |= u64::from((x >> bit) & 1) << (2 * bit);
+        d |= u64::from((y >> bit) & 1) << (2 * bit + 1);
+    }
+    d
+}
@@ -1,4 +1,31 @@
-use crate::tree::Node;
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::intersections;
+use crate::intersections::Crossings;
+use crate::point_tree::PointTree;
+use crate::polygon::Boundary;
+use crate::predicates::Precision;
+use crate::tree::{Node, Tree};
+use crate::weighting::Weighting;
+
+// nearest edge to a reference point
+// polygon is the index of the polygon the edge belongs to
+// index is the vertex index of the edge start point
+// t is the parameter (between 0 and 1) along the edge of the closest point (x, y)
+#[cfg_attr(feature = "pyo3", pyclass(get_all))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestEdge {
+    pub polygon: usize,
+    pub index: usize,
+    pub t: f64,
+    pub x: f64,
+    pub y: f64,
+    pub distance: f64,
+}
 
 fn box_distance(p: (f64, f64), node: &Node) -> f64 {
     let difx = if p.0 < node.xmin {
@@ -20,7 +47,45 @@ fn box_distance(p: (f64, f64), node: &Node) -> f64 {
     distance(difx, dify)
 }
 
-pub fn get_distance_edge(node: &Node, d: f64, p: (f64, f64)) -> f64 {
+// children of node with the one with the smallest lower bound of its distance
+// first, so that a close candidate is found early and more of the remaining
+// nodes can be pruned
+// sorting all children would need an allocation in each node
+// nodes is the node array of the tree node belongs to
+fn nearest_first<'a>(
+    nodes: &'a [Node],
+    node: &Node,
+    lower_bound: impl Fn(&Node) -> f64,
+) -> impl Iterator<Item = &'a Node> {
+    let children = &nodes[node.children_nodes.clone()];
+
+    let mut first = 0;
+    let mut d_first = f64::MAX;
+    for (k, child_node) in children.iter().enumerate() {
+        let d = lower_bound(child_node);
+        if d < d_first {
+            first = k;
+            d_first = d;
+        }
+    }
+
+    // leaves have no children and give an empty iterator
+    children.get(first).into_iter().chain(
+        children
+            .iter()
+            .enumerate()
+            .filter(move |(k, _)| *k != first)
+            .map(|(_, child_node)| child_node),
+    )
+}
+
+pub fn get_distance_edge<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    d: f64,
+    p: (f64, f64),
+    boundary: Boundary,
+) -> f64 {
     if box_distance(p, node) > d {
         return d;
     }
@@ -28,27 +93,187 @@ pub fn get_distance_edge(node: &Node, d: f64, p: (f64, f64)) -> f64 {
     let mut d_min = d;
 
     if !node.children_nodes.is_empty() {
-        for child_node in node.children_nodes.iter() {
-            let temp = get_distance_edge(child_node, d_min, p);
+        for child_node in nearest_first(&tree.nodes, node, |n| box_distance(p, n)) {
+            let temp = get_distance_edge(tree, child_node, d_min, p, boundary);
             d_min = d_min.min(temp);
         }
         return d_min;
     }
 
-    if !node.edges.is_empty() {
-        for edge in &node.edges {
-            d_min = d_min.min(dsegment(
-                p.0, p.1, edge.p1.x, edge.p1.y, edge.p2.x, edge.p2.y,
-            ));
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        if boundary.includes(e.interior[i]) {
+            d_min = d_min.min(dsegment(p.0, p.1, x1, y1, x2, y2));
         }
-        return d_min;
     }
 
     d_min
 }
 
-pub fn get_distance_vertex(node: &Node, i: usize, d: f64, p: (f64, f64)) -> (usize, f64) {
+// like get_distance_edge but h is interpolated linearly between the two end
+// points of each edge and added to the distance, see dsegment_h
+// h along an edge is never below node.hmin so boxes are pruned as for vertices
+pub fn get_distance_edge_h<T>(tree: &Tree<T>, node: &Node, d: f64, p: (f64, f64)) -> f64 {
     if box_distance(p, node) + node.hmin > d {
+        return d;
+    }
+
+    let mut d_min = d;
+
+    if !node.children_nodes.is_empty() {
+        for child_node in nearest_first(&tree.nodes, node, |n| box_distance(p, n) + n.hmin) {
+            let temp = get_distance_edge_h(tree, child_node, d_min, p);
+            d_min = d_min.min(temp);
+        }
+        return d_min;
+    }
+
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        d_min = d_min.min(dsegment_h(p, (x1, y1, e.h1[i]), (x2, y2, e.h2[i])));
+    }
+
+    d_min
+}
+
+// combines get_distance_edge and intersections::num_intersections so that
+// both are computed in one traversal
+// a node is skipped only if neither of the two needs it
+// crossings are summed up so the children can be visited nearest first
+pub fn get_distance_edge_and_intersections<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    d: f64,
+    c: Crossings,
+    p: (f64, f64),
+    precision: Precision,
+) -> (f64, Crossings) {
+    let need_distance = box_distance(p, node) <= d;
+    let need_intersections = !intersections::skip_box_intersection(p, node);
+
+    if !need_distance && !need_intersections {
+        return (d, c);
+    }
+
+    let mut d_min = d;
+    let mut c_ = c;
+
+    if !node.children_nodes.is_empty() {
+        for child_node in nearest_first(&tree.nodes, node, |n| box_distance(p, n)) {
+            (d_min, c_) =
+                get_distance_edge_and_intersections(tree, child_node, d_min, c_, p, precision);
+        }
+        return (d_min, c_);
+    }
+
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        if need_distance {
+            d_min = d_min.min(dsegment(p.0, p.1, x1, y1, x2, y2));
+        }
+        if need_intersections {
+            c_.add(intersections::stored_edge_intersections(p, e, i, precision));
+        }
+    }
+
+    (d_min, c_)
+}
+
+// same idea as get_distance_edge_and_intersections but instead of the distance
+// we only need to know whether some edge is within the tolerance
+// returns true as soon as such an edge is found, otherwise the intersections
+// are accumulated in c
+pub fn get_boundary_or_intersections<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    tolerance: f64,
+    c: &mut Crossings,
+    p: (f64, f64),
+    precision: Precision,
+) -> bool {
+    let need_boundary = box_distance(p, node) <= tolerance;
+    let need_intersections = !intersections::skip_box_intersection(p, node);
+
+    if !need_boundary && !need_intersections {
+        return false;
+    }
+
+    for child_node in tree.children(node) {
+        if get_boundary_or_intersections(tree, child_node, tolerance, c, p, precision) {
+            return true;
+        }
+    }
+
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        if need_boundary
+            && (intersections::is_on_edge(p, &e.get(i), precision)
+                || dsegment(p.0, p.1, x1, y1, x2, y2) <= tolerance)
+        {
+            return true;
+        }
+        if need_intersections {
+            c.add(intersections::stored_edge_intersections(p, e, i, precision));
+        }
+    }
+
+    false
+}
+
+pub fn get_nearest_edge<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    nearest: Option<NearestEdge>,
+    p: (f64, f64),
+) -> Option<NearestEdge> {
+    let d = nearest.map_or(f64::MAX, |n| n.distance);
+
+    if box_distance(p, node) > d {
+        return nearest;
+    }
+
+    let mut nearest = nearest;
+
+    if !node.children_nodes.is_empty() {
+        for child_node in nearest_first(&tree.nodes, node, |n| box_distance(p, n)) {
+            nearest = get_nearest_edge(tree, child_node, nearest, p);
+        }
+        return nearest;
+    }
+
+    let mut d_min = d;
+
+    for i in node.edges.clone() {
+        let edge = &tree.edges.get(i);
+        let (t, x, y) = project_segment(p.0, p.1, edge.p1.x, edge.p1.y, edge.p2.x, edge.p2.y);
+        let d_t = distance(p.0 - x, p.1 - y);
+        if d_t < d_min {
+            d_min = d_t;
+            nearest = Some(NearestEdge {
+                polygon: edge.polygon,
+                index: edge.p1.index,
+                t,
+                x,
+                y,
+                distance: d_t,
+            });
+        }
+    }
+
+    nearest
+}
+
+pub fn get_distance_vertex<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    i: Option<usize>,
+    d: f64,
+    p: (f64, f64),
+    weighting: &impl Weighting,
+) -> (Option<usize>, f64) {
+    let lower_bound = |n: &Node| weighting.lower_bound(box_distance(p, n), n.hmin);
+
+    if lower_bound(node) > d {
         return (i, d);
     }
 
@@ -56,8 +281,8 @@ pub fn get_distance_vertex(node: &Node, i: usize, d: f64, p: (f64, f64)) -> (usi
     let mut d_min = d;
 
     if !node.children_nodes.is_empty() {
-        for child_node in node.children_nodes.iter() {
-            let (i_t, d_t) = get_distance_vertex(child_node, i_min, d_min, p);
+        for child_node in nearest_first(&tree.nodes, node, lower_bound) {
+            let (i_t, d_t) = get_distance_vertex(tree, child_node, i_min, d_min, p, weighting);
             if d_t < d_min {
                 d_min = d_t;
                 i_min = i_t;
@@ -66,50 +291,341 @@ pub fn get_distance_vertex(node: &Node, i: usize, d: f64, p: (f64, f64)) -> (usi
         return (i_min, d_min);
     }
 
-    if !node.edges.is_empty() {
-        for edge in &node.edges {
-            let d_t = distance(edge.p1.x - p.0, edge.p1.y - p.1) + edge.p1.h;
+    let e = &tree.edges;
+    for (i, x, y, h) in e.start_points(node.edges.clone()) {
+        let d_t = weighting.weight(distance(x - p.0, y - p.1), h);
+        if d_t < d_min {
+            d_min = d_t;
+            i_min = Some(e.index1[i]);
+        }
+    }
+
+    if let Some(i) = node.edges.clone().last() {
+        let d_t = weighting.weight(distance(e.x2[i] - p.0, e.y2[i] - p.1), e.h2[i]);
+        if d_t < d_min {
+            d_min = d_t;
+            i_min = Some(e.index2[i]);
+        }
+    }
+
+    (i_min, d_min)
+}
+
+// (index, distance) pair ordered by distance and then by index
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    index: usize,
+    distance: f64,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+// bounded max-heap which keeps the k closest candidates seen so far
+// the worst of them is on top and its distance is used for pruning
+pub struct KNearest {
+    k: usize,
+    heap: BinaryHeap<Candidate>,
+}
+
+impl KNearest {
+    pub fn new(k: usize) -> KNearest {
+        KNearest {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    // anything farther away than this cannot enter the heap anymore
+    fn bound(&self) -> f64 {
+        if self.heap.len() < self.k {
+            f64::MAX
+        } else {
+            self.heap.peek().map_or(f64::MAX, |c| c.distance)
+        }
+    }
+
+    fn push(&mut self, index: usize, distance: f64) {
+        if distance >= self.bound() {
+            return;
+        }
+
+        // vertices at leaf boundaries are visited twice
+        if self.heap.iter().any(|c| c.index == index) {
+            return;
+        }
+
+        self.heap.push(Candidate { index, distance });
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    // indices and distances sorted by increasing distance
+    pub fn into_sorted(self) -> (Vec<usize>, Vec<f64>) {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|c| (c.index, c.distance))
+            .unzip()
+    }
+}
+
+pub fn get_k_nearest_edges<T>(tree: &Tree<T>, node: &Node, nearest: &mut KNearest, p: (f64, f64)) {
+    if box_distance(p, node) > nearest.bound() {
+        return;
+    }
+
+    for child_node in nearest_first(&tree.nodes, node, |n| box_distance(p, n)) {
+        get_k_nearest_edges(tree, child_node, nearest, p);
+    }
+
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        nearest.push(e.index1[i], dsegment(p.0, p.1, x1, y1, x2, y2));
+    }
+}
+
+pub fn get_k_nearest_vertices<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    nearest: &mut KNearest,
+    p: (f64, f64),
+    weighting: &impl Weighting,
+) {
+    let lower_bound = |n: &Node| weighting.lower_bound(box_distance(p, n), n.hmin);
+
+    if lower_bound(node) > nearest.bound() {
+        return;
+    }
+
+    for child_node in nearest_first(&tree.nodes, node, lower_bound) {
+        get_k_nearest_vertices(tree, child_node, nearest, p, weighting);
+    }
+
+    let e = &tree.edges;
+    for (i, x, y, h) in e.start_points(node.edges.clone()) {
+        nearest.push(e.index1[i], weighting.weight(distance(x - p.0, y - p.1), h));
+    }
+
+    if let Some(i) = node.edges.clone().last() {
+        nearest.push(
+            e.index2[i],
+            weighting.weight(distance(e.x2[i] - p.0, e.y2[i] - p.1), e.h2[i]),
+        );
+    }
+}
+
+// same pruning as get_distance_vertex but over the points of a point tree
+// the returned index is the position of the point in the input
+pub fn get_nearest_point(
+    tree: &PointTree,
+    node: &Node,
+    i: Option<usize>,
+    d: f64,
+    p: (f64, f64),
+    weighting: &impl Weighting,
+) -> (Option<usize>, f64) {
+    let lower_bound = |n: &Node| weighting.lower_bound(box_distance(p, n), n.hmin);
+
+    if lower_bound(node) > d {
+        return (i, d);
+    }
+
+    let mut i_min = i;
+    let mut d_min = d;
+
+    if !node.children_nodes.is_empty() {
+        for child_node in nearest_first(&tree.nodes, node, lower_bound) {
+            let (i_t, d_t) = get_nearest_point(tree, child_node, i_min, d_min, p, weighting);
             if d_t < d_min {
                 d_min = d_t;
-                i_min = edge.p1.index;
+                i_min = i_t;
             }
         }
+        return (i_min, d_min);
+    }
 
-        let edge = node.edges.last().unwrap();
-        let d_t = distance(edge.p2.x - p.0, edge.p2.y - p.1) + edge.p2.h;
+    for (i, x, y, h) in tree.points(node.edges.clone()) {
+        let d_t = weighting.weight(distance(x - p.0, y - p.1), h);
         if d_t < d_min {
             d_min = d_t;
-            i_min = edge.p2.index;
+            i_min = Some(tree.index[i]);
         }
-
-        return (i_min, d_min);
     }
 
     (i_min, d_min)
 }
 
+pub fn get_k_nearest_points(
+    tree: &PointTree,
+    node: &Node,
+    nearest: &mut KNearest,
+    p: (f64, f64),
+    weighting: &impl Weighting,
+) {
+    let lower_bound = |n: &Node| weighting.lower_bound(box_distance(p, n), n.hmin);
+
+    if lower_bound(node) > nearest.bound() {
+        return;
+    }
+
+    for child_node in nearest_first(&tree.nodes, node, lower_bound) {
+        get_k_nearest_points(tree, child_node, nearest, p, weighting);
+    }
+
+    for (i, x, y, h) in tree.points(node.edges.clone()) {
+        nearest.push(
+            tree.index[i],
+            weighting.weight(distance(x - p.0, y - p.1), h),
+        );
+    }
+}
+
+// collects start vertex indices of all edges within distance r
+pub fn get_edges_within<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    r: f64,
+    p: (f64, f64),
+    indices: &mut Vec<usize>,
+) {
+    if box_distance(p, node) > r {
+        return;
+    }
+
+    for child_node in tree.children(node) {
+        get_edges_within(tree, child_node, r, p, indices);
+    }
+
+    let e = &tree.edges;
+    for (i, x1, y1, x2, y2) in e.segments(node.edges.clone()) {
+        if dsegment(p.0, p.1, x1, y1, x2, y2) <= r {
+            indices.push(e.index1[i]);
+        }
+    }
+}
+
+// collects indices of all vertices within distance r (including h)
+// vertices at leaf boundaries can be collected twice
+pub fn get_vertices_within<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    r: f64,
+    p: (f64, f64),
+    indices: &mut Vec<usize>,
+) {
+    if box_distance(p, node) + node.hmin > r {
+        return;
+    }
+
+    for child_node in tree.children(node) {
+        get_vertices_within(tree, child_node, r, p, indices);
+    }
+
+    let e = &tree.edges;
+    for (i, x, y, h) in e.start_points(node.edges.clone()) {
+        if distance(x - p.0, y - p.1) + h <= r {
+            indices.push(e.index1[i]);
+        }
+    }
+
+    if let Some(i) = node.edges.clone().last()
+        && distance(e.x2[i] - p.0, e.y2[i] - p.1) + e.h2[i] <= r
+    {
+        indices.push(e.index2[i]);
+    }
+}
+
 fn distance(x: f64, y: f64) -> f64 {
     (x * x + y * y).sqrt()
 }
 
+fn dsegment(x0: f64, y0: f64, p1x: f64, p1y: f64, p2x: f64, p2y: f64) -> f64 {
+    let (_, x, y) = project_segment(x0, y0, p1x, p1y, p2x, p2y);
+    distance(x0 - x, y0 - y)
+}
+
+// smallest distance from p to a point of the segment plus h at that point
+// where h changes linearly from p1.2 to p2.2
+// both terms are convex along the segment so the minimum of the unconstrained
+// problem is clamped to the segment: with s the signed distance along the line
+// from the foot of the perpendicular and d the perpendicular distance, the
+// derivative vanishes where s / sqrt(s^2 + d^2) = -(h2 - h1) / length
+// if h changes faster than the distance can, the minimum is at an end point
+fn dsegment_h(p: (f64, f64), p1: (f64, f64, f64), p2: (f64, f64, f64)) -> f64 {
+    let v = (p2.0 - p1.0, p2.1 - p1.1);
+    let w = (p.0 - p1.0, p.1 - p1.1);
+    let slope = p2.2 - p1.2;
+
+    let c2 = v.0 * v.0 + v.1 * v.1;
+    if c2 == 0.0 {
+        return distance(w.0, w.1) + p1.2.min(p2.2);
+    }
+    let length = c2.sqrt();
+
+    let c = -slope / length;
+    let t = if c >= 1.0 {
+        1.0
+    } else if c <= -1.0 {
+        0.0
+    } else {
+        let t_foot = (v.0 * w.0 + v.1 * w.1) / c2;
+        let d_perp = (v.0 * w.1 - v.1 * w.0).abs() / length;
+        let s = c * d_perp / (1.0 - c * c).sqrt();
+        (t_foot + s / length).clamp(0.0, 1.0)
+    };
+
+    // the end point itself avoids the rounding of p1 + (p2 - p1)
+    let (x, y) = if t >= 1.0 {
+        (p2.0, p2.1)
+    } else {
+        (p1.0 + t * v.0, p1.1 + t * v.1)
+    };
+
+    distance(p.0 - x, p.1 - y) + (p1.2 + t * slope)
+}
+
+// returns the parameter t along the segment and the point on the segment
+// closest to (x0, y0)
 // this is derived from a C/C++ code
 // Copyright (C) 2004-2012 Per-Olof Persson
 // which was shared under GPL
-fn dsegment(x0: f64, y0: f64, p1x: f64, p1y: f64, p2x: f64, p2y: f64) -> f64 {
+fn project_segment(x0: f64, y0: f64, p1x: f64, p1y: f64, p2x: f64, p2y: f64) -> (f64, f64, f64) {
     let v = (p2x - p1x, p2y - p1y);
     let w = (x0 - p1x, y0 - p1y);
 
     let c1 = v.0 * w.0 + v.1 * w.1;
 
     if c1 <= 0.0 {
-        return distance(x0 - p1x, y0 - p1y);
+        return (0.0, p1x, p1y);
     }
 
     let c2 = v.0 * v.0 + v.1 * v.1;
 
     if c1 >= c2 {
-        distance(x0 - p2x, y0 - p2y)
+        (1.0, p2x, p2y)
     } else {
-        distance(x0 - (p1x + c1 / c2 * v.0), y0 - (p1y + c1 / c2 * v.1))
+        let t = c1 / c2;
+        (t, p1x + t * v.0, p1y + t * v.1)
     }
 }
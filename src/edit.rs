@@ -0,0 +1,447 @@
+// inserting and removing polygons of a built tree without rebuilding it
+//
+// a leaf only ever holds edges of one ring, so polygons are inserted and
+// removed as whole leaves and all leaves stay at the same depth
+// nodes and edges which are no longer used stay in their arrays until there
+// are more unused than used ones, then the arrays are compacted
+// after an edit the children of a node may come before it in Tree::nodes,
+// compacting restores the order in which the tree is built and saved
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::error::{BuildError, QueryError};
+use crate::polygon::Ring;
+use crate::tree;
+use crate::tree::{Edges, Node, Tree};
+
+// axis-aligned box around all rings of one polygon
+// used to find the leaves of a polygon without visiting the whole tree
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    pub xmin: f64,
+    pub xmax: f64,
+    pub ymin: f64,
+    pub ymax: f64,
+}
+
+impl Bounds {
+    fn new() -> Bounds {
+        Bounds {
+            xmin: f64::MAX,
+            xmax: -f64::MAX,
+            ymin: f64::MAX,
+            ymax: -f64::MAX,
+        }
+    }
+
+    fn add(&mut self, x: f64, y: f64) {
+        self.xmin = self.xmin.min(x);
+        self.xmax = self.xmax.max(x);
+        self.ymin = self.ymin.min(y);
+        self.ymax = self.ymax.max(y);
+    }
+
+    fn overlaps(&self, node: &Node) -> bool {
+        self.xmin <= node.xmax
+            && node.xmin <= self.xmax
+            && self.ymin <= node.ymax
+            && node.ymin <= self.ymax
+    }
+}
+
+pub fn polygon_bounds_from_rings(rings: &[Ring]) -> HashMap<usize, Bounds> {
+    let mut bounds = HashMap::new();
+    for ring in rings {
+        let b = bounds.entry(ring.polygon).or_insert_with(Bounds::new);
+        for &(x, y, _) in &ring.points {
+            b.add(x, y);
+        }
+    }
+    bounds
+}
+
+// offset is the index of the first vertex of the first ring
+pub fn first_vertices_from_rings(rings: &[Ring], offset: usize) -> BTreeMap<usize, Option<usize>> {
+    let mut first_vertices = BTreeMap::new();
+    let mut offset = offset;
+    for ring in rings {
+        if ring.ring == 0 {
+            first_vertices.insert(offset, Some(ring.polygon));
+        }
+        offset += ring.num_input_points;
+    }
+    first_vertices
+}
+
+pub fn polygon_bounds_from_edges(edges: &Edges) -> HashMap<usize, Bounds> {
+    let mut bounds = HashMap::new();
+    for (i, x1, y1, x2, y2) in edges.segments(0..edges.len()) {
+        let b = bounds.entry(edges.polygon[i]).or_insert_with(Bounds::new);
+        b.add(x1, y1);
+        b.add(x2, y2);
+    }
+    bounds
+}
+
+// the rings become one new polygon whose id is returned
+// ids and vertex indices continue after the largest ones ever used in the tree
+pub fn insert_rings<T>(tree: &mut Tree<T>, mut rings: Vec<Ring>) -> Result<usize, BuildError> {
+    let polygon = tree.num_polygons;
+    // an id without rings could neither be found nor removed
+    if rings.is_empty() {
+        return Err(BuildError::EmptyPolygon { polygon });
+    }
+    for ring in &mut rings {
+        ring.polygon = polygon;
+    }
+
+    tree::check_input(&rings, tree.num_edges_children, tree.num_nodes_children)?;
+    tree.num_polygons += 1;
+    tree.polylines |= rings.iter().any(|ring| !ring.closed);
+
+    tree.polygon_bounds
+        .extend(polygon_bounds_from_rings(&rings));
+    if tree.polygon_ids {
+        tree.first_vertices
+            .extend(first_vertices_from_rings(&rings, tree.num_vertices));
+    }
+
+    let mut leaves = Vec::new();
+    for ring in &rings {
+        leaves.append(&mut tree::group_edges(
+            tree.num_edges_children,
+            tree::points_to_edges(ring, tree.num_vertices),
+            &mut tree.edges,
+        ));
+        tree.num_vertices += ring.num_input_points;
+    }
+
+    if !tree.polygon_ids {
+        tree.edges.polygon.clear();
+    }
+
+    for leaf in leaves {
+        insert_leaf(tree, leaf);
+    }
+    tree.compact = false;
+
+    if is_too_deep(tree) {
+        rebuild_above_leaves(tree);
+    } else {
+        compact_if_mostly_garbage(tree);
+    }
+
+    Ok(polygon)
+}
+
+pub fn remove_polygon<T>(tree: &mut Tree<T>, polygon: usize) -> Result<(), QueryError> {
+    if !tree.polygon_ids {
+        return Err(QueryError::MissingPolygonIds);
+    }
+    let Some(bounds) = tree.polygon_bounds.remove(&polygon) else {
+        return Err(QueryError::UnknownPolygon { polygon });
+    };
+    tree.payloads.remove(&polygon);
+    // the vertices stay in the map without owner, otherwise they would
+    // fall to the polygon before
+    for owner in tree.first_vertices.values_mut() {
+        if *owner == Some(polygon) {
+            *owner = None;
+        }
+    }
+
+    // leaves of nodes which became too small and need to be inserted again
+    let mut orphans = Vec::new();
+
+    if remove_below(tree, 0, polygon, &bounds, &mut orphans) {
+        tree.nodes.clear();
+        tree.garbage_nodes = 0;
+    }
+
+    // a root with a single child is replaced by that child
+    while let Some(root) = tree.nodes.first()
+        && root.children_nodes.len() == 1
+    {
+        let child = root.children_nodes.start;
+        tree.nodes[0] = tree.nodes[child].clone();
+        tree.garbage_nodes += 1;
+    }
+
+    for leaf in orphans {
+        insert_leaf(tree, leaf);
+    }
+    tree.compact = false;
+
+    if tree.nodes.is_empty() {
+        tree.nodes = Vec::new();
+        tree.edges = Edges::default();
+        tree.garbage_nodes = 0;
+        tree.garbage_edges = 0;
+        tree.compact = true;
+    } else {
+        compact_if_mostly_garbage(tree);
+    }
+
+    Ok(())
+}
+
+// splits can leave nodes with few children behind, so with few children per
+// node and insertions which keep going into the same region the tree can get
+// much deeper than a fresh build
+fn is_too_deep<T>(tree: &Tree<T>) -> bool {
+    let Some(mut node) = tree.root() else {
+        return false;
+    };
+    let mut depth = 1;
+    while let Some(child) = tree.children(node).first() {
+        depth += 1;
+        node = child;
+    }
+
+    // every leaf has at most num_edges_children edges
+    let mut num_nodes = (tree.edges.len() - tree.garbage_edges) / tree.num_edges_children;
+    let mut fresh_depth = 1;
+    while num_nodes > 1 {
+        num_nodes = num_nodes.div_ceil(tree.num_nodes_children);
+        fresh_depth += 1;
+    }
+
+    depth > 2 * fresh_depth
+}
+
+// groups the leaves into new levels the same way the tree is built
+fn rebuild_above_leaves<T>(tree: &mut Tree<T>) {
+    let mut leaves = Vec::new();
+    collect_leaves(tree, 0, &mut leaves);
+
+    let levels = tree::build_levels(leaves, tree.num_nodes_children, tree.bulk_loading);
+    tree.nodes = tree::flatten_levels(levels);
+    tree.garbage_nodes = 0;
+    compact(tree);
+}
+
+fn compact_if_mostly_garbage<T>(tree: &mut Tree<T>) {
+    if 2 * tree.garbage_nodes > tree.nodes.len() || 2 * tree.garbage_edges > tree.edges.len() {
+        compact(tree);
+    }
+}
+
+// stores the nodes in the order of a fresh build and drops all unused
+// nodes and edges
+pub fn compact<T>(tree: &mut Tree<T>) {
+    let mut nodes = Vec::with_capacity(tree.nodes.len() - tree.garbage_nodes);
+    let mut edges = Edges::default();
+
+    if let Some(root) = tree.root() {
+        nodes.push(root.clone());
+    }
+
+    // breadth-first so that children come after their parent and next to each other
+    let mut k = 0;
+    while k < nodes.len() {
+        let children = nodes[k].children_nodes.clone();
+        if children.is_empty() {
+            let first = edges.len();
+            for i in nodes[k].edges.clone() {
+                edges.push(&tree.edges.get(i));
+            }
+            nodes[k].edges = first..edges.len();
+        } else {
+            let first = nodes.len();
+            nodes.extend_from_slice(&tree.nodes[children]);
+            nodes[k].children_nodes = first..nodes.len();
+        }
+        k += 1;
+    }
+
+    if !tree.polygon_ids {
+        edges.polygon = Vec::new();
+    }
+
+    tree.nodes = nodes;
+    tree.edges = edges;
+    tree.garbage_nodes = 0;
+    tree.garbage_edges = 0;
+    tree.compact = true;
+}
+
+fn insert_leaf<T>(tree: &mut Tree<T>, leaf: Node) {
+    if tree.nodes.is_empty() {
+        tree.nodes.push(leaf);
+        return;
+    }
+
+    // the root was split and the old root and its new sibling
+    // become the children of a new root
+    if let Some(sibling) = insert_below(tree, 0, leaf) {
+        let first = tree.nodes.len();
+        tree.nodes.push(tree.nodes[0].clone());
+        tree.nodes.push(sibling);
+
+        let mut root = Node::new();
+        root.children_nodes = first..tree.nodes.len();
+        tree.nodes[0] = root;
+        refit(tree, 0);
+    }
+}
+
+// returns a node which has to become a sibling of node i
+// which is the new leaf itself if i is a leaf or the other half of i if
+// i had to be split
+fn insert_below<T>(tree: &mut Tree<T>, i: usize, leaf: Node) -> Option<Node> {
+    if tree.nodes[i].children_nodes.is_empty() {
+        return Some(leaf);
+    }
+
+    let child = least_enlargement(tree, i, &leaf);
+    let split = insert_below(tree, child, leaf).and_then(|sibling| add_child(tree, i, sibling));
+    refit(tree, i);
+    split
+}
+
+// the child of node i whose box grows least when it takes the leaf,
+// ties go to the smaller box
+fn least_enlargement<T>(tree: &Tree<T>, i: usize, leaf: &Node) -> usize {
+    let area = |xmin: f64, xmax: f64, ymin: f64, ymax: f64| (xmax - xmin) * (ymax - ymin);
+
+    tree.nodes[i]
+        .children_nodes
+        .clone()
+        .map(|j| {
+            let c = &tree.nodes[j];
+            let before = area(c.xmin, c.xmax, c.ymin, c.ymax);
+            let after = area(
+                c.xmin.min(leaf.xmin),
+                c.xmax.max(leaf.xmax),
+                c.ymin.min(leaf.ymin),
+                c.ymax.max(leaf.ymax),
+            );
+            (j, after - before, before)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.2.total_cmp(&b.2)))
+        .map(|(j, _, _)| j)
+        .unwrap()
+}
+
+// if node i has no room for another child, its children are split into two
+// halves along the longer side of their box and the second half is returned
+// as a new node which has to be added to the parent of i
+fn add_child<T>(tree: &mut Tree<T>, i: usize, child: Node) -> Option<Node> {
+    let children = tree.nodes[i].children_nodes.clone();
+
+    // children at the end of the array can grow in place
+    if children.len() < tree.num_nodes_children && children.end == tree.nodes.len() {
+        tree.nodes.push(child);
+        tree.nodes[i].children_nodes = children.start..tree.nodes.len();
+        return None;
+    }
+
+    let mut group = tree.nodes[children.clone()].to_vec();
+    group.push(child);
+    tree.garbage_nodes += children.len();
+
+    if group.len() <= tree.num_nodes_children {
+        tree.nodes[i].children_nodes = append_nodes(tree, group);
+        return None;
+    }
+
+    let mut bounds = Bounds::new();
+    for node in &group {
+        bounds.add(node.xmin + node.xmax, node.ymin + node.ymax);
+    }
+    if bounds.xmax - bounds.xmin > bounds.ymax - bounds.ymin {
+        group.sort_by(|a, b| (a.xmin + a.xmax).total_cmp(&(b.xmin + b.xmax)));
+    } else {
+        group.sort_by(|a, b| (a.ymin + a.ymax).total_cmp(&(b.ymin + b.ymax)));
+    }
+    let second_half = group.split_off(group.len() / 2);
+
+    tree.nodes[i].children_nodes = append_nodes(tree, group);
+
+    let mut sibling = Node::new();
+    sibling.children_nodes = append_nodes(tree, second_half);
+    for node in &tree.nodes[sibling.children_nodes.clone()] {
+        sibling.adjust_bounds(node.xmin, node.xmax, node.ymin, node.ymax, node.hmin);
+    }
+
+    Some(sibling)
+}
+
+fn append_nodes<T>(tree: &mut Tree<T>, nodes: Vec<Node>) -> std::ops::Range<usize> {
+    let first = tree.nodes.len();
+    tree.nodes.extend(nodes);
+    first..tree.nodes.len()
+}
+
+fn refit<T>(tree: &mut Tree<T>, i: usize) {
+    let mut node = Node::new();
+    for child in tree.children(&tree.nodes[i]) {
+        node.adjust_bounds(child.xmin, child.xmax, child.ymin, child.ymax, child.hmin);
+    }
+    node.children_nodes = tree.nodes[i].children_nodes.clone();
+    tree.nodes[i] = node;
+}
+
+// returns true if node i has to be removed from its parent because nothing
+// is left below it or because it became too small and its remaining leaves
+// were moved to orphans
+fn remove_below<T>(
+    tree: &mut Tree<T>,
+    i: usize,
+    polygon: usize,
+    bounds: &Bounds,
+    orphans: &mut Vec<Node>,
+) -> bool {
+    let node = &tree.nodes[i];
+    if !bounds.overlaps(node) {
+        return false;
+    }
+
+    if node.children_nodes.is_empty() {
+        if tree.edges.polygon[node.edges.start] != polygon {
+            return false;
+        }
+        tree.garbage_edges += node.edges.len();
+        return true;
+    }
+
+    let children = node.children_nodes.clone();
+    let mut kept = children.start;
+    for j in children.clone() {
+        if remove_below(tree, j, polygon, bounds, orphans) {
+            tree.garbage_nodes += 1;
+        } else {
+            if kept != j {
+                tree.nodes[kept] = tree.nodes[j].clone();
+            }
+            kept += 1;
+        }
+    }
+
+    tree.nodes[i].children_nodes = children.start..kept;
+
+    if kept == children.start {
+        return true;
+    }
+
+    // the root may have any number of children
+    if i > 0 && kept - children.start < tree.num_nodes_children / 2 {
+        collect_leaves(tree, i, orphans);
+        return true;
+    }
+
+    refit(tree, i);
+    false
+}
+
+// moves the leaves below node i to orphans, the other nodes become unused
+fn collect_leaves<T>(tree: &mut Tree<T>, i: usize, orphans: &mut Vec<Node>) {
+    for j in tree.nodes[i].children_nodes.clone() {
+        if tree.nodes[j].children_nodes.is_empty() {
+            orphans.push(tree.nodes[j].clone());
+        } else {
+            collect_leaves(tree, j, orphans);
+        }
+        tree.garbage_nodes += 1;
+    }
+}
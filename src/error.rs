@@ -0,0 +1,188 @@
+use std::error::Error;
+use std::fmt;
+
+// errors which can happen while building the search tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    // a ring needs at least three points, not counting repeated points
+    // ring is 0 for the exterior ring and counts holes and parts from 1
+    DegeneratePolygon {
+        polygon: usize,
+        ring: usize,
+        num_points: usize,
+    },
+    // a polyline needs at least two points, not counting repeated points
+    DegeneratePolyline {
+        polyline: usize,
+        num_points: usize,
+    },
+    // num_edges_children needs to be at least 1 and num_nodes_children at least 2
+    InvalidFanOut {
+        parameter: &'static str,
+        value: usize,
+    },
+    // x, y, or h of a point is NaN or infinite
+    NonFiniteCoordinate {
+        polygon: usize,
+        ring: usize,
+        point: usize,
+    },
+    // the tolerance needs to be finite and not negative
+    InvalidTolerance {
+        value: f64,
+    },
+    // x, y, or h of a point of a point tree is NaN or infinite
+    NonFinitePoint {
+        point: usize,
+    },
+    // a polygon inserted into a built tree needs at least one ring
+    EmptyPolygon {
+        polygon: usize,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DegeneratePolygon {
+                polygon,
+                ring,
+                num_points,
+            } => write!(
+                f,
+                "ring {} of polygon {} has {} point(s) but at least 3 are needed",
+                ring, polygon, num_points
+            ),
+            BuildError::DegeneratePolyline {
+                polyline,
+                num_points,
+            } => write!(
+                f,
+                "polyline {} has {} point(s) but at least 2 are needed",
+                polyline, num_points
+            ),
+            BuildError::InvalidFanOut { parameter, value } => {
+                write!(f, "{} cannot be {}", parameter, value)
+            }
+            BuildError::NonFiniteCoordinate {
+                polygon,
+                ring,
+                point,
+            } => write!(
+                f,
+                "point {} of ring {} of polygon {} has a NaN or infinite coordinate",
+                point, ring, polygon
+            ),
+            BuildError::InvalidTolerance { value } => {
+                write!(f, "tolerance cannot be {}", value)
+            }
+            BuildError::NonFinitePoint { point } => {
+                write!(f, "point {} has a NaN or infinite coordinate", point)
+            }
+            BuildError::EmptyPolygon { polygon } => {
+                write!(f, "polygon {} has no rings", polygon)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+// errors which can happen while loading a serialized search tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    // the data does not start with the expected magic bytes
+    InvalidMagic,
+    // the data was written by a newer version of the format
+    UnsupportedVersion(u32),
+    // the data ends before the tree is complete
+    UnexpectedEnd,
+    // there is data left after the tree
+    TrailingBytes,
+    // the data is complete but does not describe a valid tree
+    Invalid(&'static str),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::InvalidMagic => write!(f, "data is not a serialized search tree"),
+            LoadError::UnsupportedVersion(version) => {
+                write!(f, "unsupported format version {}", version)
+            }
+            LoadError::UnexpectedEnd => write!(f, "data ends unexpectedly"),
+            LoadError::TrailingBytes => write!(f, "data continues after the end of the tree"),
+            LoadError::Invalid(reason) => write!(f, "invalid search tree: {}", reason),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+// errors which can happen when a query or an edit needs something the tree does not have
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    // the tree was built with store_polygon_ids(false)
+    MissingPolygonIds,
+    // no polygon with this id is in the tree
+    UnknownPolygon { polygon: usize },
+    // points cannot be inside or outside of open polylines
+    OpenPolylines,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::MissingPolygonIds => {
+                write!(f, "the tree was built without storing polygon ids")
+            }
+            QueryError::UnknownPolygon { polygon } => {
+                write!(f, "polygon {} is not in the tree", polygon)
+            }
+            QueryError::OpenPolylines => write!(
+                f,
+                "the tree contains open polylines so points are neither inside nor outside"
+            ),
+        }
+    }
+}
+
+impl Error for QueryError {}
+
+// errors which can happen while reading polygons from other formats
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadError {
+    // the input does not follow its format
+    Malformed(String),
+    // a geometry of a type which the reader cannot use, for instance a
+    // point when building a polygon tree
+    // feature is the position of the geometry in the input
+    UnsupportedGeometry {
+        feature: usize,
+        geometry: &'static str,
+    },
+    // the geometries were read but do not give a valid tree
+    Build(BuildError),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Malformed(reason) => write!(f, "malformed input: {}", reason),
+            ReadError::UnsupportedGeometry { feature, geometry } => write!(
+                f,
+                "feature {} is a {} which is not supported here",
+                feature, geometry
+            ),
+            ReadError::Build(error) => error.fmt(f),
+        }
+    }
+}
+
+impl Error for ReadError {}
+
+impl From<BuildError> for ReadError {
+    fn from(error: BuildError) -> ReadError {
+        ReadError::Build(error)
+    }
+}
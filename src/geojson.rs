@@ -0,0 +1,147 @@
+// building a tree from GeoJSON and writing query results as GeoJSON
+// every feature becomes one polygon or polyline whose id is the position of
+// the feature and whose payload is the properties of the feature
+// Polygon and MultiPolygon geometries keep their holes, LineString and
+// MultiLineString geometries become open polylines
+// a third coordinate (altitude) is ignored
+// features without geometry get an id but are not in the tree, like null
+// shapes of shapefiles, so they have no payload either
+
+use ::geojson::{Feature, FeatureCollection, GeoJson, Geometry, JsonObject, JsonValue, Value};
+
+use crate::builder::TreeBuilder;
+use crate::error::{QueryError, ReadError};
+use crate::polygon::Ring;
+use crate::tree;
+use crate::tree::Tree;
+
+pub(crate) fn build(
+    builder: &TreeBuilder,
+    geojson: &GeoJson,
+) -> Result<Tree<JsonObject>, ReadError> {
+    let features: Vec<(Option<&Geometry>, Option<&JsonObject>)> = match geojson {
+        GeoJson::FeatureCollection(collection) => collection
+            .features
+            .iter()
+            .map(|feature| (feature.geometry.as_ref(), feature.properties.as_ref()))
+            .collect(),
+        GeoJson::Feature(feature) => vec![(feature.geometry.as_ref(), feature.properties.as_ref())],
+        GeoJson::Geometry(geometry) => vec![(Some(geometry), None)],
+    };
+
+    let mut rings = Vec::new();
+    for (i, (geometry, _)) in features.iter().enumerate() {
+        if let Some(geometry) = geometry {
+            rings.append(&mut rings_from_value(i, &geometry.value)?);
+        }
+    }
+
+    let tree = tree::build_tree(rings, features.len(), builder)?;
+    let properties = features
+        .into_iter()
+        .map(|(_, properties)| properties.cloned().unwrap_or_default());
+
+    Ok(tree.with_payloads(properties))
+}
+
+fn rings_from_value(feature: usize, value: &Value) -> Result<Vec<Ring>, ReadError> {
+    let mut rings = Vec::new();
+
+    match value {
+        Value::Polygon(polygon) => push_polygon(&mut rings, feature, polygon)?,
+        Value::MultiPolygon(polygons) => {
+            for polygon in polygons {
+                push_polygon(&mut rings, feature, polygon)?;
+            }
+        }
+        Value::LineString(line) => rings.push(Ring::polyline(points(feature, line)?, feature)),
+        Value::MultiLineString(lines) => {
+            for (k, line) in lines.iter().enumerate() {
+                let mut ring = Ring::polyline(points(feature, line)?, feature);
+                ring.ring = k;
+                rings.push(ring);
+            }
+        }
+        _ => {
+            return Err(ReadError::UnsupportedGeometry {
+                feature,
+                geometry: value.type_name(),
+            });
+        }
+    }
+
+    Ok(rings)
+}
+
+// the first ring is the exterior and the remaining rings are holes
+// rings are numbered across all polygons of a feature
+fn push_polygon(
+    rings: &mut Vec<Ring>,
+    feature: usize,
+    polygon: &[Vec<Vec<f64>>],
+) -> Result<(), ReadError> {
+    for (k, ring) in polygon.iter().enumerate() {
+        let n = rings.len();
+        rings.push(Ring::new(points(feature, ring)?, feature, n, k > 0));
+    }
+    Ok(())
+}
+
+fn points(feature: usize, positions: &[Vec<f64>]) -> Result<Vec<(f64, f64, f64)>, ReadError> {
+    positions
+        .iter()
+        .map(|position| match position[..] {
+            [x, y, ..] => Ok((x, y, 0.0)),
+            _ => Err(ReadError::Malformed(format!(
+                "feature {} has a position with fewer than two coordinates",
+                feature
+            ))),
+        })
+        .collect()
+}
+
+// one point feature per point with the properties inside (only if the tree
+// has no polylines), distance (to the nearest edge), nearest_index (vertex
+// index of the start point of the nearest edge), and nearest_polygon (id of
+// the polygon with the nearest edge, for a tree built from GeoJSON the
+// position of its feature)
+// distance, nearest_index, and nearest_polygon are null if the tree is empty
+// fails with QueryError::MissingPolygonIds if the tree was built without
+// polygon ids
+pub fn points_to_geojson<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<FeatureCollection, QueryError> {
+    let nearest = tree::try_nearest_edges(tree, points)?;
+    let inside = if tree.has_polylines() {
+        None
+    } else {
+        Some(tree::try_points_are_inside(tree, points)?)
+    };
+
+    let features = points
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| {
+            let mut feature = Feature::from(Value::Point(vec![x, y]));
+            if let Some(inside) = &inside {
+                feature.set_property("inside", inside[i]);
+            }
+            match nearest[i] {
+                Some(edge) => {
+                    feature.set_property("distance", edge.distance);
+                    feature.set_property("nearest_index", edge.index);
+                    feature.set_property("nearest_polygon", edge.polygon);
+                }
+                None => {
+                    feature.set_property("distance", JsonValue::Null);
+                    feature.set_property("nearest_index", JsonValue::Null);
+                    feature.set_property("nearest_polygon", JsonValue::Null);
+                }
+            }
+            feature
+        })
+        .collect();
+
+    Ok(features)
+}
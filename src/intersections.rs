@@ -1,43 +1,153 @@
-use crate::tree::{Edge, Node};
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+use crate::predicates;
+use crate::predicates::Precision;
+use crate::tree::{Edge, Edges, Node, Tree};
 use float_cmp::approx_eq;
 
+// which points count as inside when rings overlap or intersect themselves
+#[cfg_attr(feature = "pyo3", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    EvenOdd,
+    NonZero,
+}
+
+// where a point is with respect to the polygons
+#[cfg_attr(feature = "pyo3", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Inside,
+    Outside,
+    OnBoundary,
+}
+
 // we count each intersection twice since this makes it possible to deal with the reference point
 // has the same y-coordinate as an edge point and then we can avoid double-counting the
 // intersection
-pub fn num_intersections(node: &Node, n: i32, p: (f64, f64)) -> i32 {
+// n counts all intersections and w counts intersections with upward edges minus
+// intersections with downward edges (the winding number)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crossings {
+    pub n: i32,
+    pub w: i32,
+}
+
+impl Crossings {
+    pub fn add(&mut self, other: Crossings) {
+        self.n += other.n;
+        self.w += other.w;
+    }
+}
+
+impl FillRule {
+    // the division by 2 is because we count each edge intersection twice
+    pub fn is_inside(self, c: Crossings) -> bool {
+        match self {
+            FillRule::EvenOdd => ((c.n / 2) % 2) != 0,
+            FillRule::NonZero => (c.w / 2) != 0,
+        }
+    }
+}
+
+pub fn num_intersections<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    c: Crossings,
+    p: (f64, f64),
+    precision: Precision,
+) -> Crossings {
     if skip_box_intersection(p, node) {
-        return n;
+        return c;
     }
 
-    let mut n_ = n;
+    let mut c_ = c;
 
     if !node.children_nodes.is_empty() {
-        for child_node in &node.children_nodes {
-            n_ = num_intersections(child_node, n_, p);
+        for child_node in tree.children(node) {
+            c_ = num_intersections(tree, child_node, c_, p, precision);
         }
-        return n_;
-    }
-
-    if !node.edges.is_empty() {
-        for edge in &node.edges {
-            if crosses(p, edge) {
-                // if y-coordinate of reference point is equal to y-coordinate of edge point
-                if (approx_eq!(f64, p.1, edge.p1.y, ulps = 2) && edge.p1.in_between)
-                    || (approx_eq!(f64, p.1, edge.p2.y, ulps = 2) && edge.p2.in_between)
-                {
-                    n_ += 1;
-                } else {
-                    n_ += 2;
-                }
+        return c_;
+    }
+
+    for i in node.edges.clone() {
+        c_.add(stored_edge_intersections(p, &tree.edges, i, precision));
+    }
+
+    c_
+}
+
+// edge_intersections for edge i of the tree
+// most edges are above or below the point and we skip them
+// before gathering the whole edge
+pub fn stored_edge_intersections(
+    p: (f64, f64),
+    edges: &Edges,
+    i: usize,
+    precision: Precision,
+) -> Crossings {
+    let (y1, y2) = (edges.y1[i], edges.y2[i]);
+    if p.1 > y1.max(y2) || p.1 < y1.min(y2) {
+        return Crossings::default();
+    }
+    edge_intersections(p, &edges.get(i), precision)
+}
+
+// same as num_intersections but the intersections are counted separately
+// for each polygon, counts holds (polygon, intersections) pairs
+pub fn polygon_intersections<T>(
+    tree: &Tree<T>,
+    node: &Node,
+    p: (f64, f64),
+    precision: Precision,
+    counts: &mut Vec<(usize, Crossings)>,
+) {
+    if skip_box_intersection(p, node) {
+        return;
+    }
+
+    for child_node in tree.children(node) {
+        polygon_intersections(tree, child_node, p, precision, counts);
+    }
+
+    for i in node.edges.clone() {
+        let c = stored_edge_intersections(p, &tree.edges, i, precision);
+        if c.n > 0 {
+            let edge_polygon = tree.edges.polygon[i];
+            match counts
+                .iter_mut()
+                .find(|(polygon, _)| *polygon == edge_polygon)
+            {
+                Some((_, count)) => count.add(c),
+                None => counts.push((edge_polygon, c)),
             }
         }
-        return n_;
     }
+}
+
+pub fn edge_intersections(p: (f64, f64), edge: &Edge, precision: Precision) -> Crossings {
+    let direction = crosses(p, edge, precision);
+    if direction == 0 {
+        return Crossings::default();
+    }
+
+    // if y-coordinate of reference point is equal to y-coordinate of edge point
+    let n = if (approx_eq!(f64, p.1, edge.p1.y, ulps = 2) && edge.p1.in_between)
+        || (approx_eq!(f64, p.1, edge.p2.y, ulps = 2) && edge.p2.in_between)
+    {
+        1
+    } else {
+        2
+    };
 
-    n
+    Crossings {
+        n,
+        w: direction * n,
+    }
 }
 
-fn skip_box_intersection(p: (f64, f64), node: &Node) -> bool {
+pub fn skip_box_intersection(p: (f64, f64), node: &Node) -> bool {
     if p.0 < node.xmin {
         return true;
     }
@@ -50,11 +160,27 @@ fn skip_box_intersection(p: (f64, f64), node: &Node) -> bool {
     false
 }
 
+// true if r lies exactly on the edge
+pub fn is_on_edge(r: (f64, f64), e: &Edge, precision: Precision) -> bool {
+    if r.0 < e.p1.x.min(e.p2.x) || r.0 > e.p1.x.max(e.p2.x) {
+        return false;
+    }
+    if r.1 < e.p1.y.min(e.p2.y) || r.1 > e.p1.y.max(e.p2.y) {
+        return false;
+    }
+    a_z(r, e, precision) == 0.0
+}
+
 // a_z is one component of the vector cross product
 // a_z < 0 for r right of the (upward) line p1-p2
 // a_z > 0 for r left of the (upward) line p1-p2
 // a_z = 0 if r lies on the line p1-p2
-fn a_z(r: (f64, f64), e: &Edge) -> f64 {
+// with Precision::Robust the sign of a_z is exact
+fn a_z(r: (f64, f64), e: &Edge, precision: Precision) -> f64 {
+    if precision == Precision::Robust {
+        return predicates::orient2d((e.p1.x, e.p1.y), (e.p2.x, e.p2.y), r);
+    }
+
     let b_x = e.p2.x - e.p1.x;
     let b_y = e.p2.y - e.p1.y;
 
@@ -73,24 +199,26 @@ fn a_z(r: (f64, f64), e: &Edge) -> f64 {
 // SoftSurfer makes no warranty for this code, and cannot be held
 // liable for any real or imagined damage resulting from its use.
 // Users of this code must verify correctness for their application.
-fn crosses(r: (f64, f64), e: &Edge) -> bool {
+// returns 1 if a horizontal line from the left to the reference point crosses an
+// upward edge, -1 if it crosses a downward edge, and 0 otherwise
+fn crosses(r: (f64, f64), e: &Edge, precision: Precision) -> i32 {
     // reference point is above the edge so a horizontal line to the point
     // cannot crosse the edge
     if r.1 > e.p1.y.max(e.p2.y) {
-        return false;
+        return 0;
     }
 
     // reference point is below the edge so a horizontal line to the point
     // cannot crosse the edge
     if r.1 < e.p1.y.min(e.p2.y) {
-        return false;
+        return 0;
     }
 
     if e.p1.y < e.p2.y {
         // upward edge
-        a_z(r, e) < 0.0
+        if a_z(r, e, precision) < 0.0 { 1 } else { 0 }
     } else {
         // downward edge
-        a_z(r, e) > 0.0
+        if a_z(r, e, precision) > 0.0 { -1 } else { 0 }
     }
 }
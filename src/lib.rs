@@ -1,15 +1,100 @@
 //! Computes distances to polygon edges and vertices and can check whether points are
 //! inside/outside polygons.
 
+pub use crate::builder::TreeBuilder;
+pub use crate::bulk_loading::BulkLoading;
+pub use crate::distance::NearestEdge;
+pub use crate::error::BuildError;
+pub use crate::error::LoadError;
+pub use crate::error::QueryError;
+pub use crate::error::ReadError;
+#[cfg(feature = "geojson")]
+pub use crate::geojson::points_to_geojson;
+pub use crate::intersections::FillRule;
+pub use crate::intersections::Location;
+pub use crate::point_tree::PointTree;
+pub use crate::point_tree::build_point_tree;
+pub use crate::point_tree::build_point_tree_h;
+pub use crate::point_tree::distances_nearest_points;
+pub use crate::point_tree::distances_nearest_points_weighted;
+pub use crate::point_tree::k_nearest_points;
+pub use crate::point_tree::k_nearest_points_weighted;
+pub use crate::point_tree::try_build_point_tree;
+pub use crate::point_tree::try_build_point_tree_h;
+pub use crate::polygon::Boundary;
+pub use crate::polygon::Geometry;
+pub use crate::polygon::MultiPolygon;
+pub use crate::polygon::Polygon;
+pub use crate::predicates::Precision;
+#[cfg(feature = "shapefile")]
+pub use crate::shapefile::Attribute;
+#[cfg(feature = "shapefile")]
+pub use crate::shapefile::Attributes;
+pub use crate::tree::Tree;
+pub use crate::tree::all_containing_polygons;
 pub use crate::tree::build_search_tree;
 pub use crate::tree::build_search_tree_h;
+pub use crate::tree::build_search_tree_multipolygons;
+pub use crate::tree::build_search_tree_polylines;
+pub use crate::tree::classify_points;
+pub use crate::tree::containing_payloads;
+pub use crate::tree::containing_polygons;
 pub use crate::tree::distances_nearest_edges;
+pub use crate::tree::distances_nearest_edges_h;
+pub use crate::tree::distances_nearest_edges_with_boundary;
 pub use crate::tree::distances_nearest_vertices;
+pub use crate::tree::distances_nearest_vertices_weighted;
+pub use crate::tree::edges_within;
+pub use crate::tree::k_nearest_edges;
+pub use crate::tree::k_nearest_vertices;
+pub use crate::tree::k_nearest_vertices_weighted;
+pub use crate::tree::nearest_edges;
 pub use crate::tree::points_are_inside;
-pub use crate::tree::Tree;
+pub use crate::tree::points_are_inside_with_fill_rule;
+pub use crate::tree::signed_distances;
+pub use crate::tree::try_all_containing_polygons;
+pub use crate::tree::try_build_search_tree;
+pub use crate::tree::try_build_search_tree_h;
+pub use crate::tree::try_build_search_tree_multipolygons;
+pub use crate::tree::try_build_search_tree_polylines;
+pub use crate::tree::try_classify_points;
+pub use crate::tree::try_containing_payloads;
+pub use crate::tree::try_containing_polygons;
+pub use crate::tree::try_nearest_edges;
+pub use crate::tree::try_points_are_inside;
+pub use crate::tree::try_points_are_inside_with_fill_rule;
+pub use crate::tree::try_signed_distances;
+pub use crate::tree::vertices_within;
+pub use crate::weighting::Additive;
+pub use crate::weighting::Capped;
+pub use crate::weighting::Multiplicative;
+pub use crate::weighting::Weighting;
+#[cfg(feature = "wkb")]
+pub use crate::wkb::parse_ewkb;
+#[cfg(feature = "wkb")]
+pub use crate::wkb::parse_wkb;
+#[cfg(feature = "wkt")]
+pub use crate::wkt::parse_wkt;
 
+mod builder;
+mod bulk_loading;
 mod distance;
+mod edit;
+mod error;
+#[cfg(feature = "geojson")]
+mod geojson;
 mod intersections;
+mod point_tree;
+mod polygon;
+mod predicates;
 #[cfg(feature = "pyo3")]
 mod python;
+mod serialize;
+#[cfg(feature = "shapefile")]
+mod shapefile;
 mod tree;
+mod weighting;
+#[cfg(feature = "wkb")]
+mod wkb;
+#[cfg(feature = "wkt")]
+mod wkt;
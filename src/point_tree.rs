@@ -0,0 +1,227 @@
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::builder::TreeBuilder;
+use crate::bulk_loading;
+use crate::distance;
+use crate::error::BuildError;
+use crate::polygon;
+use crate::tree;
+use crate::tree::Node;
+use crate::weighting::{Additive, Weighting};
+use std::ops::Range;
+
+// search tree over a set of points without edges, for instance weather
+// stations
+// nodes are stored like in Tree, the edges range of a leaf node is the
+// range of its points in x, y, h, and index
+// h is added to the distance to each point for custom (weighted) distances
+// index is the position of each point in the input since the points are
+// stored in leaf order
+#[cfg_attr(feature = "pyo3", pyclass(module = "polygons"))]
+#[derive(Debug, Clone, Default)]
+pub struct PointTree {
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) x: Vec<f64>,
+    pub(crate) y: Vec<f64>,
+    pub(crate) h: Vec<f64>,
+    pub(crate) index: Vec<usize>,
+}
+
+impl PointTree {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    // number of bytes used by the tree including the node and point arrays
+    pub fn memory_usage(&self) -> usize {
+        size_of::<PointTree>()
+            + self.nodes.capacity() * size_of::<Node>()
+            + (self.x.capacity() + self.y.capacity() + self.h.capacity()) * size_of::<f64>()
+            + self.index.capacity() * size_of::<usize>()
+    }
+
+    pub(crate) fn root(&self) -> Option<&Node> {
+        self.nodes.first()
+    }
+
+    // (i, x, y, h) for the points in range
+    pub(crate) fn points(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (usize, f64, f64, f64)> + '_ {
+        let x = &self.x[range.clone()];
+        let y = &self.y[range.clone()];
+        let h = &self.h[range.clone()];
+        range
+            .zip(x.iter().zip(y).zip(h))
+            .map(|(i, ((&x, &y), &h))| (i, x, y, h))
+    }
+}
+
+// num_points_children is the number of points in each leaf node
+pub fn build_point_tree(
+    points: Vec<(f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+) -> PointTree {
+    match try_build_point_tree(points, num_points_children, num_nodes_children) {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn build_point_tree_h(
+    points: Vec<(f64, f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+) -> PointTree {
+    match try_build_point_tree_h(points, num_points_children, num_nodes_children) {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_build_point_tree(
+    points: Vec<(f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+) -> Result<PointTree, BuildError> {
+    try_build_point_tree_h(
+        polygon::pad(points),
+        num_points_children,
+        num_nodes_children,
+    )
+}
+
+pub fn try_build_point_tree_h(
+    points: Vec<(f64, f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+) -> Result<PointTree, BuildError> {
+    TreeBuilder::new()
+        .num_edges_children(num_points_children)
+        .num_nodes_children(num_nodes_children)
+        .build_points_h(points)
+}
+
+// every point starts as a node without extent, these are sorted like the
+// leaves of a polygon tree and then grouped into the actual leaves
+pub(crate) fn build(
+    points: Vec<(f64, f64, f64)>,
+    builder: &TreeBuilder,
+) -> Result<PointTree, BuildError> {
+    let num_points_children = builder.num_edges_children;
+    let num_nodes_children = builder.num_nodes_children;
+
+    tree::check_fan_out(num_points_children, num_nodes_children)?;
+
+    if let Some(point) = points
+        .iter()
+        .position(|(x, y, h)| !(x.is_finite() && y.is_finite() && h.is_finite()))
+    {
+        return Err(BuildError::NonFinitePoint { point });
+    }
+
+    let mut point_nodes: Vec<Node> = points
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y, h))| {
+            let mut node = Node::new();
+            node.adjust_bounds(x, x, y, y, h);
+            node.edges = i..(i + 1);
+            node
+        })
+        .collect();
+    bulk_loading::sort_nodes(
+        &mut point_nodes,
+        num_points_children,
+        builder.bulk_loading,
+        true,
+    );
+
+    let mut tree = PointTree::default();
+    let mut leaves = Vec::new();
+    for chunk in point_nodes.chunks(num_points_children) {
+        let mut leaf = Node::new();
+        let first = tree.x.len();
+        for node in chunk {
+            let i = node.edges.start;
+            let (x, y, h) = points[i];
+            leaf.adjust_bounds(x, x, y, y, h);
+            tree.x.push(x);
+            tree.y.push(y);
+            tree.h.push(h);
+            tree.index.push(i);
+        }
+        leaf.edges = first..tree.x.len();
+        leaves.push(leaf);
+    }
+
+    // the points of each leaf stay where they are even if the leaves are
+    // sorted once more while grouping them
+    let levels = tree::build_levels(leaves, num_nodes_children, builder.bulk_loading);
+    tree.nodes = tree::flatten_levels(levels);
+
+    Ok(tree)
+}
+
+// index (position in the input) and distance of the nearest point for each
+// point, the distance includes h of the nearest point
+// the index is None only if the tree has no points
+pub fn distances_nearest_points(
+    tree: &PointTree,
+    points: &[(f64, f64)],
+) -> (Vec<Option<usize>>, Vec<f64>) {
+    distances_nearest_points_weighted(tree, points, &Additive)
+}
+
+// the distances combine the distance to each point with its h as given by
+// weighting, distances_nearest_points uses Additive
+pub fn distances_nearest_points_weighted(
+    tree: &PointTree,
+    points: &[(f64, f64)],
+    weighting: &(impl Weighting + Sync),
+) -> (Vec<Option<usize>>, Vec<f64>) {
+    let Some(root) = tree.root() else {
+        return (vec![None; points.len()], vec![f64::INFINITY; points.len()]);
+    };
+
+    let large_number = f64::MAX;
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    iter.map(|p| distance::get_nearest_point(tree, root, None, large_number, *p, weighting))
+        .unzip()
+}
+
+// indices and distances (including h) of the k nearest points for each point
+// sorted by increasing distance
+pub fn k_nearest_points(
+    tree: &PointTree,
+    points: &[(f64, f64)],
+    k: usize,
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    k_nearest_points_weighted(tree, points, k, &Additive)
+}
+
+pub fn k_nearest_points_weighted(
+    tree: &PointTree,
+    points: &[(f64, f64)],
+    k: usize,
+    weighting: &(impl Weighting + Sync),
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    tree::k_nearest_for_points(tree.root(), points, k, |root, nearest, p| {
+        distance::get_k_nearest_points(tree, root, nearest, p, weighting)
+    })
+}
@@ -0,0 +1,181 @@
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+// a polygon with one exterior ring and any number of interior rings (holes)
+// rings may but need not repeat the first point at the end
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<(f64, f64)>,
+    pub interiors: Vec<Vec<(f64, f64)>>,
+}
+
+// a multipolygon is one or several polygons which are reported as one
+pub type MultiPolygon = Vec<Polygon>;
+
+// one polygon, multipolygon, or open polyline, for instance read from WKT
+// or WKB, every geometry gets one id when building a tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Polygon(Polygon),
+    MultiPolygon(MultiPolygon),
+    LineString(Vec<(f64, f64)>),
+}
+
+impl From<Vec<(f64, f64)>> for Polygon {
+    fn from(exterior: Vec<(f64, f64)>) -> Polygon {
+        Polygon {
+            exterior,
+            interiors: Vec::new(),
+        }
+    }
+}
+
+// which rings to consider when computing distances to edges
+#[cfg_attr(feature = "pyo3", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    All,
+    Exterior,
+    Interior,
+}
+
+impl Boundary {
+    pub fn includes(self, interior: bool) -> bool {
+        match self {
+            Boundary::All => true,
+            Boundary::Exterior => !interior,
+            Boundary::Interior => interior,
+        }
+    }
+}
+
+// one closed ring or open polyline as it enters the tree construction
+// polygon is the index of the (multi)polygon or polyline which owns the ring
+// ring counts the rings within that (multi)polygon
+// points are normalized and positions holds the index of each point in the
+// ring as the caller gave it, num_input_points is the length of that ring
+// only closed rings get an edge from the last point back to the first
+pub struct Ring {
+    pub points: Vec<(f64, f64, f64)>,
+    pub positions: Vec<usize>,
+    pub num_input_points: usize,
+    pub polygon: usize,
+    pub ring: usize,
+    pub interior: bool,
+    pub closed: bool,
+}
+
+impl Ring {
+    // drops points which repeat the point before them and a last point which
+    // repeats the first one, so rings may be given closed or open
+    pub fn new(input: Vec<(f64, f64, f64)>, polygon: usize, ring: usize, interior: bool) -> Ring {
+        let mut closed = Ring::polyline(input, polygon);
+
+        while closed.points.len() > 1 && closed.points.last() == closed.points.first() {
+            closed.points.pop();
+            closed.positions.pop();
+        }
+
+        closed.ring = ring;
+        closed.interior = interior;
+        closed.closed = true;
+        closed
+    }
+
+    // drops points which repeat the point before them
+    // a polyline which ends where it starts keeps its last point
+    pub fn polyline(input: Vec<(f64, f64, f64)>, polyline: usize) -> Ring {
+        let num_input_points = input.len();
+        let mut points = Vec::with_capacity(num_input_points);
+        let mut positions = Vec::with_capacity(num_input_points);
+
+        for (i, point) in input.into_iter().enumerate() {
+            if points.last() != Some(&point) {
+                points.push(point);
+                positions.push(i);
+            }
+        }
+
+        Ring {
+            points,
+            positions,
+            num_input_points,
+            polygon: polyline,
+            ring: 0,
+            interior: false,
+            closed: false,
+        }
+    }
+}
+
+// every input polygon becomes a polygon with a single exterior ring
+pub fn rings_from_polygons(polygons: Vec<Vec<(f64, f64, f64)>>) -> Vec<Ring> {
+    polygons
+        .into_iter()
+        .enumerate()
+        .map(|(i, points)| Ring::new(points, i, 0, false))
+        .collect()
+}
+
+// polylines get the ids which follow those of the polygons
+pub fn rings_from_polylines(polylines: Vec<Vec<(f64, f64, f64)>>, first_id: usize) -> Vec<Ring> {
+    polylines
+        .into_iter()
+        .enumerate()
+        .map(|(i, points)| Ring::polyline(points, first_id + i))
+        .collect()
+}
+
+pub fn rings_from_multipolygons(multipolygons: Vec<MultiPolygon>) -> Vec<Ring> {
+    let mut rings = Vec::new();
+
+    for (i, multipolygon) in multipolygons.into_iter().enumerate() {
+        push_multipolygon(&mut rings, multipolygon, i);
+    }
+
+    rings
+}
+
+// rings are numbered across all polygons of the multipolygon
+fn push_multipolygon(rings: &mut Vec<Ring>, multipolygon: MultiPolygon, id: usize) {
+    let mut ring = 0;
+    for polygon in multipolygon {
+        rings.push(Ring::new(pad(polygon.exterior), id, ring, false));
+        ring += 1;
+
+        for interior in polygon.interiors {
+            rings.push(Ring::new(pad(interior), id, ring, true));
+            ring += 1;
+        }
+    }
+}
+
+// geometries without points (EMPTY in WKT) have no rings, their ids are
+// never reported
+pub fn rings_from_geometries(geometries: Vec<Geometry>) -> Vec<Ring> {
+    let mut rings = Vec::new();
+
+    for (i, geometry) in geometries.into_iter().enumerate() {
+        match geometry {
+            Geometry::Polygon(polygon) => {
+                if !polygon.exterior.is_empty() {
+                    push_multipolygon(&mut rings, vec![polygon], i);
+                }
+            }
+            Geometry::MultiPolygon(multipolygon) => {
+                push_multipolygon(&mut rings, multipolygon, i);
+            }
+            Geometry::LineString(points) => {
+                if !points.is_empty() {
+                    rings.push(Ring::polyline(pad(points), i));
+                }
+            }
+        }
+    }
+
+    rings
+}
+
+pub fn pad(points: Vec<(f64, f64)>) -> Vec<(f64, f64, f64)> {
+    points.into_iter().map(|(x, y)| (x, y, 0.0)).collect()
+}
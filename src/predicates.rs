@@ -0,0 +1,109 @@
+#[cfg(feature = "pyo3")]
+use pyo3::prelude::*;
+
+// how the orientation of a point with respect to an edge is computed
+// Fast uses plain floating point arithmetic
+// Robust gives the exact sign also for (nearly) collinear points
+#[cfg_attr(feature = "pyo3", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    #[default]
+    Fast,
+    Robust,
+}
+
+// half of the machine epsilon, as in Shewchuk's paper
+const EPSILON: f64 = f64::EPSILON * 0.5;
+const CCW_ERROR_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+
+// positive if c is left of the line a-b, negative if it is right of it,
+// and zero if the three points are collinear
+//
+// this follows the adaptive orientation test in
+// J. R. Shewchuk, "Adaptive Precision Floating-Point Arithmetic and Fast Robust
+// Geometric Predicates", Discrete & Computational Geometry 18, 305-363 (1997)
+// but falls back directly to the exact expansion if the floating point
+// result cannot be trusted
+pub fn orient2d(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    let detleft = (b.0 - a.0) * (c.1 - a.1);
+    let detright = (b.1 - a.1) * (c.0 - a.0);
+    let det = detleft - detright;
+
+    // if the two products have different signs, there is no cancellation
+    let detsum = if detleft > 0.0 {
+        if detright <= 0.0 {
+            return det;
+        }
+        detleft + detright
+    } else if detleft < 0.0 {
+        if detright >= 0.0 {
+            return det;
+        }
+        -detleft - detright
+    } else {
+        return det;
+    };
+
+    let error_bound = CCW_ERROR_BOUND * detsum;
+    if det >= error_bound || -det >= error_bound {
+        return det;
+    }
+
+    orient2d_exact(a, b, c)
+}
+
+// (b - a) x (c - a) expanded into products of input coordinates
+// the a.0 * a.1 terms cancel exactly and are left out
+fn orient2d_exact(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    let products = [
+        two_product(b.0, c.1),
+        two_product(-b.0, a.1),
+        two_product(-a.0, c.1),
+        two_product(-b.1, c.0),
+        two_product(b.1, a.0),
+        two_product(a.1, c.0),
+    ];
+
+    let mut expansion = Vec::with_capacity(2 * products.len() + 1);
+    for (x, y) in products {
+        grow_expansion(&mut expansion, y);
+        grow_expansion(&mut expansion, x);
+    }
+
+    // the components do not overlap and grow in magnitude so the
+    // last non-zero component determines the sign
+    expansion
+        .iter()
+        .rev()
+        .copied()
+        .find(|&x| x != 0.0)
+        .unwrap_or(0.0)
+}
+
+// x + y = a * b exactly
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let y = a.mul_add(b, -x);
+    (x, y)
+}
+
+// x + y = a + b exactly
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let b_virtual = x - a;
+    let a_virtual = x - b_virtual;
+    let b_roundoff = b - b_virtual;
+    let a_roundoff = a - a_virtual;
+    (x, a_roundoff + b_roundoff)
+}
+
+// adds b to the expansion without any rounding error
+fn grow_expansion(expansion: &mut Vec<f64>, b: f64) {
+    let mut q = b;
+    for e in expansion.iter_mut() {
+        let (sum, error) = two_sum(q, *e);
+        *e = error;
+        q = sum;
+    }
+    expansion.push(q);
+}
@@ -1,23 +1,628 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::HashMap;
 
+use crate::builder::TreeBuilder;
+use crate::bulk_loading::BulkLoading;
+use crate::distance::NearestEdge;
+use crate::error::{BuildError, LoadError, QueryError};
+use crate::intersections::{FillRule, Location};
+use crate::point_tree;
+use crate::point_tree::PointTree;
+use crate::polygon::{Boundary, Polygon};
+use crate::predicates::Precision;
 use crate::tree;
-use crate::tree::build_search_tree;
-use crate::tree::build_search_tree_h;
 use crate::tree::Tree;
+use crate::weighting;
+use crate::weighting::{Additive, Capped, Multiplicative};
 
+// the search tree as Python sees it, the payloads are arbitrary Python objects
+#[pyclass(name = "Tree", module = "polygons")]
+#[derive(Debug)]
+struct PyTree(Tree<PyObject>);
+
+// payloads by polygon id
+type Payloads = HashMap<usize, PyObject>;
+
+// what pickle needs to restore a tree: from_bytes and its arguments
+type Reduce<'py> = (Bound<'py, PyAny>, (Bound<'py, PyBytes>, Payloads));
+
+#[pymethods]
+impl PyTree {
+    #[getter(precision)]
+    fn py_precision(&self) -> Precision {
+        self.0.precision()
+    }
+
+    #[setter(precision)]
+    fn py_set_precision(&mut self, precision: Precision) {
+        self.0.set_precision(precision);
+    }
+
+    #[getter(fill_rule)]
+    fn py_fill_rule(&self) -> FillRule {
+        self.0.fill_rule()
+    }
+
+    #[getter(tolerance)]
+    fn py_tolerance(&self) -> f64 {
+        self.0.tolerance()
+    }
+
+    #[getter(has_polygon_ids)]
+    fn py_has_polygon_ids(&self) -> bool {
+        self.0.has_polygon_ids()
+    }
+
+    #[getter(has_polylines)]
+    fn py_has_polylines(&self) -> bool {
+        self.0.has_polylines()
+    }
+
+    #[getter(num_polygons)]
+    fn py_num_polygons(&self) -> usize {
+        self.0.num_polygons()
+    }
+
+    #[getter(num_vertices)]
+    fn py_num_vertices(&self) -> usize {
+        self.0.num_vertices()
+    }
+
+    #[pyo3(name = "insert_polygon")]
+    fn py_insert_polygon(&mut self, points: Vec<(f64, f64)>) -> PyResult<usize> {
+        Ok(self.0.insert_polygon(points)?)
+    }
+
+    #[pyo3(name = "insert_polygon_h")]
+    fn py_insert_polygon_h(&mut self, points: Vec<(f64, f64, f64)>) -> PyResult<usize> {
+        Ok(self.0.insert_polygon_h(points)?)
+    }
+
+    #[pyo3(name = "insert_polyline")]
+    fn py_insert_polyline(&mut self, points: Vec<(f64, f64)>) -> PyResult<usize> {
+        Ok(self.0.insert_polyline(points)?)
+    }
+
+    #[pyo3(name = "remove_polygon")]
+    fn py_remove_polygon(&mut self, polygon: usize) -> PyResult<()> {
+        Ok(self.0.remove_polygon(polygon)?)
+    }
+
+    #[pyo3(name = "payload")]
+    fn py_payload(&self, py: Python<'_>, polygon: usize) -> Option<PyObject> {
+        self.0.payload(polygon).map(|payload| payload.clone_ref(py))
+    }
+
+    #[pyo3(name = "set_payload")]
+    fn py_set_payload(&mut self, polygon: usize, payload: PyObject) -> PyResult<Option<PyObject>> {
+        Ok(self.0.set_payload(polygon, payload)?)
+    }
+
+    #[pyo3(name = "vertex_polygon")]
+    fn py_vertex_polygon(&self, index: usize) -> Option<usize> {
+        self.0.vertex_polygon(index)
+    }
+
+    #[pyo3(name = "vertex_payload")]
+    fn py_vertex_payload(&self, py: Python<'_>, index: usize) -> Option<PyObject> {
+        self.0
+            .vertex_payload(index)
+            .map(|payload| payload.clone_ref(py))
+    }
+
+    #[pyo3(name = "to_bytes")]
+    fn py_to_bytes<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.0.to_bytes())
+    }
+
+    // the bytes do not contain the payloads, they can be passed as a dict
+    // from polygon ids to payloads
+    #[staticmethod]
+    #[pyo3(name = "from_bytes", signature = (bytes, payloads = Payloads::new()))]
+    fn py_from_bytes(bytes: &[u8], payloads: Payloads) -> PyResult<PyTree> {
+        let mut tree = Tree::from_bytes(bytes)?.with_payloads(Vec::new());
+        for (polygon, payload) in payloads {
+            tree.set_payload(polygon, payload)?;
+        }
+        Ok(PyTree(tree))
+    }
+
+    // pickle stores the tree as bytes and the payloads as a dict and restores
+    // both with from_bytes
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<Reduce<'py>> {
+        let py = slf.py();
+        let from_bytes = slf.get_type().getattr("from_bytes")?;
+        let tree = slf.borrow();
+        let bytes = tree.py_to_bytes(py);
+        let payloads = tree
+            .0
+            .payloads()
+            .map(|(polygon, payload)| (polygon, payload.clone_ref(py)))
+            .collect();
+        Ok((from_bytes, (bytes, payloads)))
+    }
+}
+
+#[pymethods]
+impl PointTree {
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
+
+// the weightings Python can pass to the nearest vertex and nearest point
+// queries, Capped also needs max_distance
+#[pyclass(module = "polygons", eq, eq_int)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Weighting {
+    Additive,
+    Multiplicative,
+    Capped,
+}
+
+// indices and distances of the k nearest vertices or points for each point
+type KNearest = (Vec<Vec<usize>>, Vec<Vec<f64>>);
+
+// a weighting together with its parameter
+#[derive(Debug, Clone, Copy)]
+enum WeightingModel {
+    Additive,
+    Multiplicative,
+    Capped(Capped),
+}
+
+impl WeightingModel {
+    fn new(weighting: Weighting, max_distance: Option<f64>) -> PyResult<WeightingModel> {
+        match (weighting, max_distance) {
+            (Weighting::Additive, None) => Ok(WeightingModel::Additive),
+            (Weighting::Multiplicative, None) => Ok(WeightingModel::Multiplicative),
+            (Weighting::Capped, Some(max_distance)) => {
+                Ok(WeightingModel::Capped(Capped { max_distance }))
+            }
+            (Weighting::Capped, None) => Err(PyValueError::new_err(
+                "the capped weighting needs max_distance",
+            )),
+            (_, Some(_)) => Err(PyValueError::new_err(
+                "max_distance is only used by the capped weighting",
+            )),
+        }
+    }
+}
+
+impl weighting::Weighting for WeightingModel {
+    fn weight(&self, d: f64, h: f64) -> f64 {
+        match self {
+            WeightingModel::Additive => Additive.weight(d, h),
+            WeightingModel::Multiplicative => Multiplicative.weight(d, h),
+            WeightingModel::Capped(capped) => capped.weight(d, h),
+        }
+    }
+
+    fn lower_bound(&self, d: f64, hmin: f64) -> f64 {
+        match self {
+            WeightingModel::Additive => Additive.lower_bound(d, hmin),
+            WeightingModel::Multiplicative => Multiplicative.lower_bound(d, hmin),
+            WeightingModel::Capped(capped) => capped.lower_bound(d, hmin),
+        }
+    }
+}
+
+impl From<BuildError> for PyErr {
+    fn from(error: BuildError) -> PyErr {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+impl From<LoadError> for PyErr {
+    fn from(error: LoadError) -> PyErr {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+impl From<QueryError> for PyErr {
+    fn from(error: QueryError) -> PyErr {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+// the options of TreeBuilder which all build functions accept as keyword arguments
+fn tree_builder(
+    num_edges_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    fill_rule: FillRule,
+    tolerance: f64,
+    store_polygon_ids: bool,
+    precision: Precision,
+) -> TreeBuilder {
+    TreeBuilder::new()
+        .num_edges_children(num_edges_children)
+        .num_nodes_children(num_nodes_children)
+        .bulk_loading(bulk_loading)
+        .fill_rule(fill_rule)
+        .tolerance(tolerance)
+        .store_polygon_ids(store_polygon_ids)
+        .precision(precision)
+}
+
+// open polylines can be added to the polygons, their ids follow those of the polygons
+#[pyfunction]
+#[pyo3(signature = (
+    polygons,
+    num_edges_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+    fill_rule = FillRule::EvenOdd,
+    tolerance = 0.0,
+    store_polygon_ids = true,
+    precision = Precision::Fast,
+    polylines = Vec::new(),
+    payloads = Vec::new(),
+))]
+#[allow(clippy::too_many_arguments)]
+fn build_search_tree(
+    polygons: Vec<Vec<(f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    fill_rule: FillRule,
+    tolerance: f64,
+    store_polygon_ids: bool,
+    precision: Precision,
+    polylines: Vec<Vec<(f64, f64)>>,
+    payloads: Vec<PyObject>,
+) -> PyResult<PyTree> {
+    let builder = tree_builder(
+        num_edges_children,
+        num_nodes_children,
+        bulk_loading,
+        fill_rule,
+        tolerance,
+        store_polygon_ids,
+        precision,
+    );
+    Ok(PyTree(
+        builder
+            .build_with_polylines(polygons, polylines)?
+            .with_payloads(payloads),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    polylines,
+    num_edges_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+    store_polygon_ids = true,
+    precision = Precision::Fast,
+    payloads = Vec::new(),
+))]
+fn build_search_tree_polylines(
+    polylines: Vec<Vec<(f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    store_polygon_ids: bool,
+    precision: Precision,
+    payloads: Vec<PyObject>,
+) -> PyResult<PyTree> {
+    let builder = tree_builder(
+        num_edges_children,
+        num_nodes_children,
+        bulk_loading,
+        FillRule::EvenOdd,
+        0.0,
+        store_polygon_ids,
+        precision,
+    );
+    Ok(PyTree(
+        builder.build_polylines(polylines)?.with_payloads(payloads),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    polygons,
+    num_edges_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+    fill_rule = FillRule::EvenOdd,
+    tolerance = 0.0,
+    store_polygon_ids = true,
+    precision = Precision::Fast,
+    payloads = Vec::new(),
+))]
+#[allow(clippy::too_many_arguments)]
+fn build_search_tree_h(
+    polygons: Vec<Vec<(f64, f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    fill_rule: FillRule,
+    tolerance: f64,
+    store_polygon_ids: bool,
+    precision: Precision,
+    payloads: Vec<PyObject>,
+) -> PyResult<PyTree> {
+    let builder = tree_builder(
+        num_edges_children,
+        num_nodes_children,
+        bulk_loading,
+        fill_rule,
+        tolerance,
+        store_polygon_ids,
+        precision,
+    );
+    Ok(PyTree(builder.build_h(polygons)?.with_payloads(payloads)))
+}
+
+// the first ring is the exterior and the remaining rings are holes
+type Rings = Vec<Vec<(f64, f64)>>;
+
+// each multipolygon is a list of polygons and each polygon is a list of rings
 #[pyfunction]
-fn points_are_inside(tree: Tree, points: Vec<(f64, f64)>) -> Vec<bool> {
-    tree::points_are_inside(&tree, &points)
+#[pyo3(signature = (
+    multipolygons,
+    num_edges_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+    fill_rule = FillRule::EvenOdd,
+    tolerance = 0.0,
+    store_polygon_ids = true,
+    precision = Precision::Fast,
+    payloads = Vec::new(),
+))]
+#[allow(clippy::too_many_arguments)]
+fn build_search_tree_multipolygons(
+    multipolygons: Vec<Vec<Rings>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+    fill_rule: FillRule,
+    tolerance: f64,
+    store_polygon_ids: bool,
+    precision: Precision,
+    payloads: Vec<PyObject>,
+) -> PyResult<PyTree> {
+    let multipolygons = multipolygons
+        .into_iter()
+        .map(|multipolygon| {
+            multipolygon
+                .into_iter()
+                .map(|mut rings| {
+                    if rings.is_empty() {
+                        return Polygon::default();
+                    }
+                    let exterior = rings.remove(0);
+                    Polygon {
+                        exterior,
+                        interiors: rings,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let builder = tree_builder(
+        num_edges_children,
+        num_nodes_children,
+        bulk_loading,
+        fill_rule,
+        tolerance,
+        store_polygon_ids,
+        precision,
+    );
+    Ok(PyTree(
+        builder
+            .build_multipolygons(multipolygons)?
+            .with_payloads(payloads),
+    ))
+}
+
+// num_points_children is the number of points in each leaf node
+#[pyfunction]
+#[pyo3(signature = (
+    points,
+    num_points_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+))]
+fn build_point_tree(
+    points: Vec<(f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+) -> PyResult<PointTree> {
+    let builder = TreeBuilder::new()
+        .num_edges_children(num_points_children)
+        .num_nodes_children(num_nodes_children)
+        .bulk_loading(bulk_loading);
+    Ok(builder.build_points(points)?)
+}
+
+#[pyfunction]
+#[pyo3(signature = (
+    points,
+    num_points_children = 4,
+    num_nodes_children = 4,
+    *,
+    bulk_loading = BulkLoading::InputOrder,
+))]
+fn build_point_tree_h(
+    points: Vec<(f64, f64, f64)>,
+    num_points_children: usize,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+) -> PyResult<PointTree> {
+    let builder = TreeBuilder::new()
+        .num_edges_children(num_points_children)
+        .num_nodes_children(num_nodes_children)
+        .bulk_loading(bulk_loading);
+    Ok(builder.build_points_h(points)?)
 }
 
+// without fill_rule the fill rule of the tree is used
 #[pyfunction]
-fn distances_nearest_vertices(tree: Tree, points: Vec<(f64, f64)>) -> (Vec<usize>, Vec<f64>) {
-    tree::distances_nearest_vertices(&tree, &points)
+#[pyo3(signature = (tree, points, fill_rule = None))]
+fn points_are_inside(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    fill_rule: Option<FillRule>,
+) -> PyResult<Vec<bool>> {
+    let fill_rule = fill_rule.unwrap_or(tree.0.fill_rule());
+    Ok(tree::try_points_are_inside_with_fill_rule(
+        &tree.0, &points, fill_rule,
+    )?)
 }
 
+// without tolerance the tolerance of the tree is used
 #[pyfunction]
-fn distances_nearest_edges(tree: Tree, points: Vec<(f64, f64)>) -> Vec<f64> {
-    tree::distances_nearest_edges(&tree, &points)
+#[pyo3(signature = (tree, points, tolerance = None))]
+fn classify_points(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    tolerance: Option<f64>,
+) -> PyResult<Vec<Location>> {
+    let tolerance = tolerance.unwrap_or(tree.0.tolerance());
+    Ok(tree::try_classify_points(&tree.0, &points, tolerance)?)
+}
+
+#[pyfunction]
+fn containing_polygons(tree: &PyTree, points: Vec<(f64, f64)>) -> PyResult<Vec<Option<usize>>> {
+    Ok(tree::try_containing_polygons(&tree.0, &points)?)
+}
+
+#[pyfunction]
+fn containing_payloads(
+    py: Python<'_>,
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+) -> PyResult<Vec<Option<PyObject>>> {
+    let payloads = tree::try_containing_payloads(&tree.0, &points)?;
+    Ok(payloads
+        .into_iter()
+        .map(|payload| payload.map(|payload| payload.clone_ref(py)))
+        .collect())
+}
+
+#[pyfunction]
+fn all_containing_polygons(tree: &PyTree, points: Vec<(f64, f64)>) -> PyResult<Vec<Vec<usize>>> {
+    Ok(tree::try_all_containing_polygons(&tree.0, &points)?)
+}
+
+// without weighting h is added to the distance
+#[pyfunction]
+#[pyo3(signature = (tree, points, weighting = Weighting::Additive, max_distance = None))]
+fn distances_nearest_vertices(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    weighting: Weighting,
+    max_distance: Option<f64>,
+) -> PyResult<(Vec<Option<usize>>, Vec<f64>)> {
+    let weighting = WeightingModel::new(weighting, max_distance)?;
+    Ok(tree::distances_nearest_vertices_weighted(
+        &tree.0, &points, &weighting,
+    ))
+}
+
+#[pyfunction]
+fn distances_nearest_edges(tree: &PyTree, points: Vec<(f64, f64)>) -> Vec<f64> {
+    tree::distances_nearest_edges(&tree.0, &points)
+}
+
+#[pyfunction]
+fn distances_nearest_edges_h(tree: &PyTree, points: Vec<(f64, f64)>) -> Vec<f64> {
+    tree::distances_nearest_edges_h(&tree.0, &points)
+}
+
+#[pyfunction]
+fn distances_nearest_edges_with_boundary(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    boundary: Boundary,
+) -> Vec<f64> {
+    tree::distances_nearest_edges_with_boundary(&tree.0, &points, boundary)
+}
+
+#[pyfunction]
+fn signed_distances(tree: &PyTree, points: Vec<(f64, f64)>) -> PyResult<Vec<f64>> {
+    Ok(tree::try_signed_distances(&tree.0, &points)?)
+}
+
+#[pyfunction]
+fn nearest_edges(tree: &PyTree, points: Vec<(f64, f64)>) -> PyResult<Vec<Option<NearestEdge>>> {
+    Ok(tree::try_nearest_edges(&tree.0, &points)?)
+}
+
+#[pyfunction]
+#[pyo3(signature = (tree, points, k, weighting = Weighting::Additive, max_distance = None))]
+fn k_nearest_vertices(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    k: usize,
+    weighting: Weighting,
+    max_distance: Option<f64>,
+) -> PyResult<KNearest> {
+    let weighting = WeightingModel::new(weighting, max_distance)?;
+    Ok(tree::k_nearest_vertices_weighted(
+        &tree.0, &points, k, &weighting,
+    ))
+}
+
+#[pyfunction]
+fn k_nearest_edges(
+    tree: &PyTree,
+    points: Vec<(f64, f64)>,
+    k: usize,
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    tree::k_nearest_edges(&tree.0, &points, k)
+}
+
+#[pyfunction]
+fn vertices_within(tree: &PyTree, points: Vec<(f64, f64)>, r: f64) -> (Vec<usize>, Vec<usize>) {
+    tree::vertices_within(&tree.0, &points, r)
+}
+
+#[pyfunction]
+fn edges_within(tree: &PyTree, points: Vec<(f64, f64)>, r: f64) -> (Vec<usize>, Vec<usize>) {
+    tree::edges_within(&tree.0, &points, r)
+}
+
+#[pyfunction]
+#[pyo3(signature = (tree, points, weighting = Weighting::Additive, max_distance = None))]
+fn distances_nearest_points(
+    tree: &PointTree,
+    points: Vec<(f64, f64)>,
+    weighting: Weighting,
+    max_distance: Option<f64>,
+) -> PyResult<(Vec<Option<usize>>, Vec<f64>)> {
+    let weighting = WeightingModel::new(weighting, max_distance)?;
+    Ok(point_tree::distances_nearest_points_weighted(
+        tree, &points, &weighting,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (tree, points, k, weighting = Weighting::Additive, max_distance = None))]
+fn k_nearest_points(
+    tree: &PointTree,
+    points: Vec<(f64, f64)>,
+    k: usize,
+    weighting: Weighting,
+    max_distance: Option<f64>,
+) -> PyResult<KNearest> {
+    let weighting = WeightingModel::new(weighting, max_distance)?;
+    Ok(point_tree::k_nearest_points_weighted(
+        tree, &points, k, &weighting,
+    ))
 }
 
 #[pymodule]
@@ -26,9 +631,37 @@ fn polygons(m: &Bound<'_, PyModule>) -> PyResult<()> {
 
     m.add_function(wrap_pyfunction!(build_search_tree, m)?)?;
     m.add_function(wrap_pyfunction!(build_search_tree_h, m)?)?;
+    m.add_function(wrap_pyfunction!(build_search_tree_multipolygons, m)?)?;
+    m.add_function(wrap_pyfunction!(build_search_tree_polylines, m)?)?;
+    m.add_function(wrap_pyfunction!(build_point_tree, m)?)?;
+    m.add_function(wrap_pyfunction!(build_point_tree_h, m)?)?;
     m.add_function(wrap_pyfunction!(points_are_inside, m)?)?;
+    m.add_function(wrap_pyfunction!(classify_points, m)?)?;
+    m.add_function(wrap_pyfunction!(containing_polygons, m)?)?;
+    m.add_function(wrap_pyfunction!(containing_payloads, m)?)?;
+    m.add_function(wrap_pyfunction!(all_containing_polygons, m)?)?;
     m.add_function(wrap_pyfunction!(distances_nearest_vertices, m)?)?;
     m.add_function(wrap_pyfunction!(distances_nearest_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(distances_nearest_edges_h, m)?)?;
+    m.add_function(wrap_pyfunction!(distances_nearest_edges_with_boundary, m)?)?;
+    m.add_function(wrap_pyfunction!(signed_distances, m)?)?;
+    m.add_function(wrap_pyfunction!(nearest_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(k_nearest_vertices, m)?)?;
+    m.add_function(wrap_pyfunction!(k_nearest_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(vertices_within, m)?)?;
+    m.add_function(wrap_pyfunction!(edges_within, m)?)?;
+    m.add_function(wrap_pyfunction!(distances_nearest_points, m)?)?;
+    m.add_function(wrap_pyfunction!(k_nearest_points, m)?)?;
+
+    m.add_class::<NearestEdge>()?;
+    m.add_class::<Boundary>()?;
+    m.add_class::<BulkLoading>()?;
+    m.add_class::<FillRule>()?;
+    m.add_class::<Location>()?;
+    m.add_class::<PointTree>()?;
+    m.add_class::<Precision>()?;
+    m.add_class::<PyTree>()?;
+    m.add_class::<Weighting>()?;
 
     Ok(())
 }
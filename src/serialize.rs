@@ -0,0 +1,508 @@
+// binary format of a search tree, all numbers are little-endian:
+//
+//   magic              8 bytes "POLYTREE"
+//   version            u32
+//   precision          u8 (0: fast, 1: robust)
+//   fill rule          u8 (0: even-odd, 1: non-zero)
+//   tolerance          f64
+//   polygon ids        u8 (0: not stored, 1: stored)
+//   polylines          u8 (0: only closed rings, 1: also open polylines)
+//   bulk loading       u8 (0: input order, 1: STR, 2: Hilbert, 3: Morton)
+//   num_edges_children u64
+//   num_nodes_children u64
+//   next polygon id    u64
+//   next vertex index  u64
+//   number of nodes    u64
+//   number of edges    u64
+//   nodes
+//   edges
+//   polygons           only if polygon ids are not stored
+//   vertex owners      only if polygon ids are stored
+//
+// nodes are stored in the same order as in Tree::nodes, a node is
+//   xmin, xmax, ymin, ymax, hmin   f64
+//   children nodes start, end      u64
+//   edges start, end               u64
+//
+// an edge is
+//   polygon                        u64, only if polygon ids are stored
+//   interior                       u8
+//   p1, p2                         points
+//
+// a point is
+//   x, y, h                        f64
+//   index                          u64
+//   in_between                     u8
+//
+// with polygon ids the polygons in the tree follow from the edges, otherwise
+// they are stored as
+//   number of polygons             u64
+//   id                             u64, for each polygon
+//   xmin, xmax, ymin, ymax         f64, for each polygon
+//
+// the vertex owners are the entries of Tree::first_vertices
+//   number of entries              u64
+//   first vertex index             u64, for each entry
+//   polygon                        u64, for each entry, u64::MAX if removed
+//
+// the version is only bumped once a format has been released
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::bulk_loading::BulkLoading;
+use crate::edit;
+use crate::edit::Bounds;
+use crate::error::LoadError;
+use crate::intersections::FillRule;
+use crate::predicates::Precision;
+use crate::tree::{Edge, Edges, Node, Point, Tree};
+
+const MAGIC: &[u8; 8] = b"POLYTREE";
+const VERSION: u32 = 1;
+
+// trees are shallow so anything deeper than this is corrupt data
+// and we refuse it before queries run out of stack
+const MAX_DEPTH: usize = 128;
+
+// number of bytes a node and an edge (without polygon id) take
+// used to refuse counts which cannot possibly fit into the remaining data
+const NODE_SIZE: usize = 5 * 8 + 4 * 8;
+const EDGE_SIZE: usize = 1 + 2 * (3 * 8 + 8 + 1);
+const POLYGON_SIZE: usize = 8 + 4 * 8;
+const OWNER_SIZE: usize = 8 + 8;
+const REMOVED: u64 = u64::MAX;
+const HEADER_SIZE: usize = 8 + 4 + 1 + 1 + 8 + 1 + 1 + 1 + 6 * 8;
+
+pub fn tree_to_bytes<T>(tree: &Tree<T>) -> Vec<u8> {
+    let edge_size = if tree.polygon_ids {
+        EDGE_SIZE + 8
+    } else {
+        EDGE_SIZE
+    };
+    let mut bytes = Vec::with_capacity(
+        HEADER_SIZE + tree.nodes.len() * NODE_SIZE + tree.edges.len() * edge_size,
+    );
+
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.push(match tree.precision {
+        Precision::Fast => 0,
+        Precision::Robust => 1,
+    });
+    bytes.push(match tree.fill_rule {
+        FillRule::EvenOdd => 0,
+        FillRule::NonZero => 1,
+    });
+    bytes.extend_from_slice(&tree.tolerance.to_le_bytes());
+    bytes.push(tree.polygon_ids as u8);
+    bytes.push(tree.polylines as u8);
+    bytes.push(match tree.bulk_loading {
+        BulkLoading::InputOrder => 0,
+        BulkLoading::SortTileRecursive => 1,
+        BulkLoading::Hilbert => 2,
+        BulkLoading::Morton => 3,
+    });
+    for n in [
+        tree.num_edges_children,
+        tree.num_nodes_children,
+        tree.num_polygons,
+        tree.num_vertices,
+        tree.nodes.len(),
+        tree.edges.len(),
+    ] {
+        bytes.extend_from_slice(&(n as u64).to_le_bytes());
+    }
+
+    for node in &tree.nodes {
+        for x in [node.xmin, node.xmax, node.ymin, node.ymax, node.hmin] {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        for i in [
+            node.children_nodes.start,
+            node.children_nodes.end,
+            node.edges.start,
+            node.edges.end,
+        ] {
+            bytes.extend_from_slice(&(i as u64).to_le_bytes());
+        }
+    }
+
+    for i in 0..tree.edges.len() {
+        let edge = tree.edges.get(i);
+        if tree.polygon_ids {
+            bytes.extend_from_slice(&(edge.polygon as u64).to_le_bytes());
+        }
+        bytes.push(edge.interior as u8);
+        write_point(&mut bytes, &edge.p1);
+        write_point(&mut bytes, &edge.p2);
+    }
+
+    if !tree.polygon_ids {
+        // sorted so that the same tree always gives the same bytes
+        let mut polygons: Vec<_> = tree.polygon_bounds.iter().collect();
+        polygons.sort_by_key(|&(&polygon, _)| polygon);
+        bytes.extend_from_slice(&(polygons.len() as u64).to_le_bytes());
+        for (&polygon, b) in polygons {
+            bytes.extend_from_slice(&(polygon as u64).to_le_bytes());
+            for x in [b.xmin, b.xmax, b.ymin, b.ymax] {
+                bytes.extend_from_slice(&x.to_le_bytes());
+            }
+        }
+    } else {
+        bytes.extend_from_slice(&(tree.first_vertices.len() as u64).to_le_bytes());
+        for (&index, &polygon) in &tree.first_vertices {
+            bytes.extend_from_slice(&(index as u64).to_le_bytes());
+            let polygon = polygon.map_or(REMOVED, |polygon| polygon as u64);
+            bytes.extend_from_slice(&polygon.to_le_bytes());
+        }
+    }
+
+    bytes
+}
+
+pub fn tree_from_bytes(bytes: &[u8]) -> Result<Tree, LoadError> {
+    let mut reader = Reader { bytes, position: 0 };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(LoadError::InvalidMagic);
+    }
+
+    let version = reader.read_u32()?;
+    if version != VERSION {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+
+    let precision = match reader.read_u8()? {
+        0 => Precision::Fast,
+        1 => Precision::Robust,
+        _ => return Err(LoadError::Invalid("unknown precision")),
+    };
+
+    let fill_rule = match reader.read_u8()? {
+        0 => FillRule::EvenOdd,
+        1 => FillRule::NonZero,
+        _ => return Err(LoadError::Invalid("unknown fill rule")),
+    };
+
+    let tolerance = reader.read_f64()?;
+    if !(tolerance >= 0.0 && tolerance.is_finite()) {
+        return Err(LoadError::Invalid("tolerance is negative or not finite"));
+    }
+
+    let polygon_ids = reader.read_bool()?;
+    let polylines = reader.read_bool()?;
+    let bulk_loading = match reader.read_u8()? {
+        0 => BulkLoading::InputOrder,
+        1 => BulkLoading::SortTileRecursive,
+        2 => BulkLoading::Hilbert,
+        3 => BulkLoading::Morton,
+        _ => return Err(LoadError::Invalid("unknown bulk loading")),
+    };
+    let edge_size = if polygon_ids {
+        EDGE_SIZE + 8
+    } else {
+        EDGE_SIZE
+    };
+
+    let num_edges_children = reader.read_usize()?;
+    let num_nodes_children = reader.read_usize()?;
+    if num_edges_children < 1 || num_nodes_children < 2 {
+        return Err(LoadError::Invalid("invalid number of children"));
+    }
+
+    let num_polygons = reader.read_usize()?;
+    let num_vertices = reader.read_usize()?;
+
+    let num_nodes = reader.read_usize()?;
+    let num_edges = reader.read_usize()?;
+    if num_nodes > reader.remaining() / NODE_SIZE
+        || num_edges > reader.remaining() / edge_size
+        || num_nodes * NODE_SIZE + num_edges * edge_size > reader.remaining()
+    {
+        return Err(LoadError::UnexpectedEnd);
+    }
+
+    let mut nodes = Vec::with_capacity(num_nodes);
+    for _ in 0..num_nodes {
+        nodes.push(read_node(&mut reader)?);
+    }
+
+    let mut edges = Edges::default();
+    for _ in 0..num_edges {
+        let polygon = if polygon_ids { reader.read_usize()? } else { 0 };
+        let interior = reader.read_bool()?;
+        let p1 = read_point(&mut reader)?;
+        let p2 = read_point(&mut reader)?;
+        edges.push(&Edge {
+            p1,
+            p2,
+            polygon,
+            interior,
+        });
+    }
+
+    let mut polygon_bounds = HashMap::new();
+    if !polygon_ids {
+        let num_polygons_in_tree = reader.read_usize()?;
+        if num_polygons_in_tree > reader.remaining() / POLYGON_SIZE {
+            return Err(LoadError::UnexpectedEnd);
+        }
+        for _ in 0..num_polygons_in_tree {
+            let polygon = reader.read_usize()?;
+            if polygon >= num_polygons {
+                return Err(LoadError::Invalid("polygon id out of range"));
+            }
+            let bounds = Bounds {
+                xmin: reader.read_f64()?,
+                xmax: reader.read_f64()?,
+                ymin: reader.read_f64()?,
+                ymax: reader.read_f64()?,
+            };
+            polygon_bounds.insert(polygon, bounds);
+        }
+    }
+
+    let mut first_vertices = BTreeMap::new();
+    if polygon_ids {
+        let num_entries = reader.read_usize()?;
+        if num_entries > reader.remaining() / OWNER_SIZE {
+            return Err(LoadError::UnexpectedEnd);
+        }
+        for _ in 0..num_entries {
+            let index = reader.read_usize()?;
+            let polygon = match reader.read_u64()? {
+                REMOVED => None,
+                polygon => Some(polygon as usize),
+            };
+            if index >= num_vertices || polygon.is_some_and(|polygon| polygon >= num_polygons) {
+                return Err(LoadError::Invalid(
+                    "polygon id or vertex index out of range",
+                ));
+            }
+            first_vertices.insert(index, polygon);
+        }
+    }
+
+    if reader.position != bytes.len() {
+        return Err(LoadError::TrailingBytes);
+    }
+
+    check_structure(&nodes, &edges)?;
+
+    // later insertions must not hand out ids or indices which are already used
+    if (polygon_ids && edges.polygon.iter().any(|&polygon| polygon >= num_polygons))
+        || edges
+            .index1
+            .iter()
+            .chain(&edges.index2)
+            .any(|&index| index >= num_vertices)
+    {
+        return Err(LoadError::Invalid(
+            "polygon id or vertex index out of range",
+        ));
+    }
+
+    if polygon_ids {
+        polygon_bounds = edit::polygon_bounds_from_edges(&edges);
+    } else {
+        edges.polygon = Vec::new();
+    }
+
+    Ok(Tree {
+        nodes,
+        edges,
+        precision,
+        fill_rule,
+        tolerance,
+        polygon_ids,
+        polylines,
+        bulk_loading,
+        payloads: Default::default(),
+        first_vertices,
+        num_edges_children,
+        num_nodes_children,
+        num_polygons,
+        num_vertices,
+        polygon_bounds,
+        garbage_nodes: 0,
+        garbage_edges: 0,
+        compact: true,
+    })
+}
+
+// the queries rely on this without checking:
+// the root is the first node, the children of a node come after it,
+// every node but the root and every edge belongs to exactly one node,
+// and children and edges lie within the box of their node
+fn check_structure(nodes: &[Node], edges: &Edges) -> Result<(), LoadError> {
+    if nodes.is_empty() {
+        if !edges.is_empty() {
+            return Err(LoadError::Invalid("tree has edges but no nodes"));
+        }
+        return Ok(());
+    }
+
+    let mut depth = vec![0; nodes.len()];
+    let mut node_is_used = vec![false; nodes.len()];
+    let mut edge_is_used = vec![false; edges.len()];
+    node_is_used[0] = true;
+
+    for (i, node) in nodes.iter().enumerate() {
+        if !node_is_used[i] {
+            return Err(LoadError::Invalid("node does not belong to the tree"));
+        }
+
+        if !(node.xmin <= node.xmax && node.ymin <= node.ymax && node.hmin.is_finite()) {
+            return Err(LoadError::Invalid("node has invalid bounds"));
+        }
+
+        // a node contains either other nodes or edges
+        if node.children_nodes.is_empty() == node.edges.is_empty() {
+            return Err(LoadError::Invalid(
+                "node must contain either children nodes or edges",
+            ));
+        }
+
+        let children = &node.children_nodes;
+        if children.start > children.end
+            || (!children.is_empty() && (children.start <= i || children.end > nodes.len()))
+        {
+            return Err(LoadError::Invalid("children nodes are out of range"));
+        }
+        for j in children.clone() {
+            if node_is_used[j] {
+                return Err(LoadError::Invalid("node has more than one parent"));
+            }
+            node_is_used[j] = true;
+
+            depth[j] = depth[i] + 1;
+            if depth[j] > MAX_DEPTH {
+                return Err(LoadError::Invalid("tree is too deep"));
+            }
+
+            let child_node = &nodes[j];
+            if !(contains(node, child_node.xmin, child_node.ymin)
+                && contains(node, child_node.xmax, child_node.ymax)
+                && child_node.hmin >= node.hmin)
+            {
+                return Err(LoadError::Invalid("child node is outside of its parent"));
+            }
+        }
+
+        if node.edges.start > node.edges.end || node.edges.end > edges.len() {
+            return Err(LoadError::Invalid("edges are out of range"));
+        }
+        for j in node.edges.clone() {
+            if edge_is_used[j] {
+                return Err(LoadError::Invalid("edge belongs to more than one node"));
+            }
+            edge_is_used[j] = true;
+
+            let edge = edges.get(j);
+            for p in [&edge.p1, &edge.p2] {
+                if !(contains(node, p.x, p.y) && p.h >= node.hmin) {
+                    return Err(LoadError::Invalid("edge is outside of its node"));
+                }
+            }
+        }
+    }
+
+    if edge_is_used.contains(&false) {
+        return Err(LoadError::Invalid("edge does not belong to the tree"));
+    }
+
+    Ok(())
+}
+
+fn contains(node: &Node, x: f64, y: f64) -> bool {
+    node.xmin <= x && x <= node.xmax && node.ymin <= y && y <= node.ymax
+}
+
+fn write_point(bytes: &mut Vec<u8>, point: &Point) {
+    for x in [point.x, point.y, point.h] {
+        bytes.extend_from_slice(&x.to_le_bytes());
+    }
+    bytes.extend_from_slice(&(point.index as u64).to_le_bytes());
+    bytes.push(point.in_between as u8);
+}
+
+fn read_node(reader: &mut Reader) -> Result<Node, LoadError> {
+    Ok(Node {
+        xmin: reader.read_f64()?,
+        xmax: reader.read_f64()?,
+        ymin: reader.read_f64()?,
+        ymax: reader.read_f64()?,
+        hmin: reader.read_f64()?,
+        children_nodes: reader.read_usize()?..reader.read_usize()?,
+        edges: reader.read_usize()?..reader.read_usize()?,
+    })
+}
+
+fn read_point(reader: &mut Reader) -> Result<Point, LoadError> {
+    let x = reader.read_f64()?;
+    let y = reader.read_f64()?;
+    let h = reader.read_f64()?;
+
+    if !(x.is_finite() && y.is_finite() && h.is_finite()) {
+        return Err(LoadError::Invalid("point has a NaN or infinite coordinate"));
+    }
+
+    Ok(Point {
+        x,
+        y,
+        h,
+        index: reader.read_usize()?,
+        in_between: reader.read_bool()?,
+    })
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], LoadError> {
+        if self.remaining() < n {
+            return Err(LoadError::UnexpectedEnd);
+        }
+        let slice = &self.bytes[self.position..self.position + n];
+        self.position += n;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, LoadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_bool(&mut self) -> Result<bool, LoadError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(LoadError::Invalid("flag is neither 0 nor 1")),
+        }
+    }
+
+    fn read_u32(&mut self) -> Result<u32, LoadError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, LoadError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, LoadError> {
+        let bytes = self.take(8)?;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_usize(&mut self) -> Result<usize, LoadError> {
+        usize::try_from(self.read_u64()?).map_err(|_| LoadError::Invalid("index is too large"))
+    }
+}
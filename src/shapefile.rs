@@ -0,0 +1,432 @@
+// reading ESRI shapefiles from the contents of their .shp and .dbf files
+// polygon and polyline shapefiles give a tree, point shapefiles a point tree
+// every shape gets the id of its record and the attributes of its .dbf
+// record as payload
+// Z and M coordinates are dropped, null shapes have no rings and no payload
+// and their ids are never reported
+
+use std::collections::HashMap;
+
+use crate::builder::TreeBuilder;
+use crate::error::ReadError;
+use crate::point_tree;
+use crate::point_tree::PointTree;
+use crate::polygon::Ring;
+use crate::tree;
+use crate::tree::Tree;
+
+// one field of a .dbf record, dates are kept as YYYYMMDD
+// empty numbers and undecided logicals (?) are Null
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attribute {
+    Text(String),
+    Number(f64),
+    Logical(bool),
+    Date(String),
+    Null,
+}
+
+// field name and value of all fields of a .dbf record
+pub type Attributes = HashMap<String, Attribute>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ShapeType {
+    Null,
+    Point,
+    PolyLine,
+    Polygon,
+}
+
+// rings of polygons (outer rings clockwise, holes counterclockwise) or parts
+// of polylines
+type Parts = Vec<Vec<(f64, f64, f64)>>;
+
+#[derive(Debug)]
+enum Shape {
+    Null,
+    Point(f64, f64),
+    Parts(Parts),
+}
+
+pub(crate) fn build(
+    builder: &TreeBuilder,
+    shp: &[u8],
+    dbf: &[u8],
+) -> Result<Tree<Attributes>, ReadError> {
+    let (shape_type, shapes) = read_shp(shp)?;
+    let records = read_records(dbf, shapes.len())?;
+
+    if !matches!(shape_type, ShapeType::PolyLine | ShapeType::Polygon) {
+        return Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: name(shape_type),
+        });
+    }
+
+    let mut rings = Vec::new();
+    for (i, shape) in shapes.into_iter().enumerate() {
+        let Shape::Parts(parts) = shape else {
+            continue;
+        };
+        for (k, points) in parts.into_iter().enumerate() {
+            if shape_type == ShapeType::Polygon {
+                let interior = signed_area(&points) > 0.0;
+                rings.push(Ring::new(points, i, k, interior));
+            } else {
+                let mut ring = Ring::polyline(points, i);
+                ring.ring = k;
+                rings.push(ring);
+            }
+        }
+    }
+
+    let tree = tree::build_tree(rings, records.len(), builder)?;
+
+    Ok(tree.with_payloads(records))
+}
+
+// the attributes are in the order of the points of the point tree, the
+// records of null shapes are dropped
+pub(crate) fn build_points(
+    builder: &TreeBuilder,
+    shp: &[u8],
+    dbf: &[u8],
+) -> Result<(PointTree, Vec<Attributes>), ReadError> {
+    let (shape_type, shapes) = read_shp(shp)?;
+    let records = read_records(dbf, shapes.len())?;
+
+    if !matches!(shape_type, ShapeType::Point) {
+        return Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: name(shape_type),
+        });
+    }
+
+    let mut points = Vec::new();
+    let mut attributes = Vec::new();
+    for (shape, record) in shapes.into_iter().zip(records) {
+        if let Shape::Point(x, y) = shape {
+            points.push((x, y, 0.0));
+            attributes.push(record);
+        }
+    }
+
+    let tree = point_tree::build(points, builder)?;
+
+    Ok((tree, attributes))
+}
+
+fn name(shape_type: ShapeType) -> &'static str {
+    match shape_type {
+        ShapeType::Null => "null shape",
+        ShapeType::Point => "point",
+        ShapeType::PolyLine => "polyline",
+        ShapeType::Polygon => "polygon",
+    }
+}
+
+// twice the area, positive for counterclockwise rings
+fn signed_area(points: &[(f64, f64, f64)]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (x1, y1, _) = points[i];
+            let (x2, y2, _) = points[(i + 1) % n];
+            x1 * y2 - x2 * y1
+        })
+        .sum()
+}
+
+fn malformed(file: &str, reason: String) -> ReadError {
+    ReadError::Malformed(format!("{} file: {}", file, reason))
+}
+
+// reads little- or big-endian numbers at given offsets
+struct Bytes<'a> {
+    bytes: &'a [u8],
+    file: &'static str,
+}
+
+impl Bytes<'_> {
+    fn array<const N: usize>(&self, offset: usize) -> Result<[u8; N], ReadError> {
+        match self
+            .bytes
+            .get(offset..)
+            .and_then(|rest| rest.first_chunk::<N>())
+        {
+            Some(array) => Ok(*array),
+            None => Err(malformed(
+                self.file,
+                format!("ends after {} bytes but more are needed", self.bytes.len()),
+            )),
+        }
+    }
+
+    fn u32_be(&self, offset: usize) -> Result<u32, ReadError> {
+        Ok(u32::from_be_bytes(self.array(offset)?))
+    }
+
+    fn u32_le(&self, offset: usize) -> Result<u32, ReadError> {
+        Ok(u32::from_le_bytes(self.array(offset)?))
+    }
+
+    fn u16_le(&self, offset: usize) -> Result<u16, ReadError> {
+        Ok(u16::from_le_bytes(self.array(offset)?))
+    }
+
+    fn f64_le(&self, offset: usize) -> Result<f64, ReadError> {
+        Ok(f64::from_le_bytes(self.array(offset)?))
+    }
+}
+
+fn shape_type(file: &Bytes, offset: usize) -> Result<ShapeType, ReadError> {
+    match file.u32_le(offset)? {
+        0 => Ok(ShapeType::Null),
+        1 | 11 | 21 => Ok(ShapeType::Point),
+        3 | 13 | 23 => Ok(ShapeType::PolyLine),
+        5 | 15 | 25 => Ok(ShapeType::Polygon),
+        8 | 18 | 28 => Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "multipoint",
+        }),
+        31 => Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "multipatch",
+        }),
+        code => Err(malformed(
+            file.file,
+            format!("unknown shape type {} at byte {}", code, offset),
+        )),
+    }
+}
+
+// the header is 100 bytes long, each record has a header of 8 bytes
+// followed by its content, lengths are in 16-bit words
+fn read_shp(shp: &[u8]) -> Result<(ShapeType, Vec<Shape>), ReadError> {
+    let file = Bytes {
+        bytes: shp,
+        file: ".shp",
+    };
+
+    if file.u32_be(0)? != 9994 {
+        return Err(malformed(".shp", "the file code is not 9994".to_string()));
+    }
+    let length = 2 * file.u32_be(24)? as usize;
+    if length < 100 || length > shp.len() {
+        return Err(malformed(
+            ".shp",
+            format!(
+                "the header gives {} bytes but there are {}",
+                length,
+                shp.len()
+            ),
+        ));
+    }
+    let file_type = shape_type(&file, 32)?;
+
+    let mut shapes = Vec::new();
+    let mut offset = 100;
+    while offset < length {
+        let content_length = 2 * file.u32_be(offset + 4)? as usize;
+        let start = offset + 8;
+        let end = start + content_length;
+        if end > length {
+            return Err(malformed(
+                ".shp",
+                format!("record at byte {} continues after the end", offset),
+            ));
+        }
+
+        let record = Bytes {
+            bytes: &shp[..end],
+            file: ".shp",
+        };
+        let shape = match shape_type(&record, start) {
+            Err(ReadError::UnsupportedGeometry { geometry, .. }) => {
+                return Err(ReadError::UnsupportedGeometry {
+                    feature: shapes.len(),
+                    geometry,
+                });
+            }
+            Err(error) => return Err(error),
+            Ok(ShapeType::Null) => Shape::Null,
+            Ok(shape_type) if shape_type != file_type => {
+                return Err(malformed(
+                    ".shp",
+                    format!(
+                        "record {} is a {} in a {} file",
+                        shapes.len(),
+                        name(shape_type),
+                        name(file_type)
+                    ),
+                ));
+            }
+            Ok(ShapeType::Point) => {
+                Shape::Point(record.f64_le(start + 4)?, record.f64_le(start + 12)?)
+            }
+            Ok(_) => Shape::Parts(read_parts(&record, start + 4)?),
+        };
+        shapes.push(shape);
+
+        offset = end;
+    }
+
+    Ok((file_type, shapes))
+}
+
+// bounding box, number of parts, number of points, index of the first point
+// of each part, and the points
+fn read_parts(record: &Bytes, offset: usize) -> Result<Parts, ReadError> {
+    let num_parts = record.u32_le(offset + 32)? as usize;
+    let num_points = record.u32_le(offset + 36)? as usize;
+    let first_point = offset + 40 + 4 * num_parts;
+
+    let mut starts = Vec::new();
+    for k in 0..num_parts {
+        starts.push(record.u32_le(offset + 40 + 4 * k)? as usize);
+    }
+    starts.push(num_points);
+
+    let valid = starts.first() == Some(&0) || num_parts == 0;
+    if !valid || starts.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(malformed(
+            ".shp",
+            format!(
+                "the parts of the record at byte {} are out of order",
+                offset
+            ),
+        ));
+    }
+
+    let mut parts = Vec::new();
+    for pair in starts.windows(2) {
+        let mut points = Vec::new();
+        for i in pair[0]..pair[1] {
+            let x = record.f64_le(first_point + 16 * i)?;
+            let y = record.f64_le(first_point + 16 * i + 8)?;
+            points.push((x, y, 0.0));
+        }
+        parts.push(points);
+    }
+
+    Ok(parts)
+}
+
+struct Field {
+    name: String,
+    kind: u8,
+    length: usize,
+}
+
+// a header of 32 bytes, field descriptors of 32 bytes each up to 0x0d, and
+// records which start with a deletion flag
+// deleted records are kept since their shapes are still in the .shp file
+fn read_records(dbf: &[u8], num_shapes: usize) -> Result<Vec<Attributes>, ReadError> {
+    let file = Bytes {
+        bytes: dbf,
+        file: ".dbf",
+    };
+
+    let num_records = file.u32_le(4)? as usize;
+    let header_length = file.u16_le(8)? as usize;
+    let record_length = file.u16_le(10)? as usize;
+
+    if num_records != num_shapes {
+        return Err(malformed(
+            ".dbf",
+            format!(
+                "there are {} records but {} shapes",
+                num_records, num_shapes
+            ),
+        ));
+    }
+
+    let mut fields = Vec::new();
+    let mut offset = 32;
+    while file.array::<1>(offset)? != [0x0d] {
+        let descriptor: [u8; 32] = file.array(offset)?;
+        let name = descriptor[..11]
+            .split(|&byte| byte == 0)
+            .next()
+            .unwrap_or(&[]);
+        fields.push(Field {
+            name: String::from_utf8_lossy(name).trim().to_string(),
+            kind: descriptor[11],
+            length: descriptor[16] as usize,
+        });
+        offset += 32;
+    }
+
+    // the records start after the terminator
+    if header_length < offset + 1 {
+        return Err(malformed(
+            ".dbf",
+            format!(
+                "the header of {} bytes ends before its field descriptors",
+                header_length
+            ),
+        ));
+    }
+    if fields.iter().map(|field| field.length).sum::<usize>() + 1 > record_length {
+        return Err(malformed(
+            ".dbf",
+            format!(
+                "the fields do not fit into records of {} bytes",
+                record_length
+            ),
+        ));
+    }
+    let records_end = num_records
+        .checked_mul(record_length)
+        .and_then(|length| length.checked_add(header_length))
+        .ok_or_else(|| malformed(".dbf", "the records do not fit into memory".to_string()))?;
+    if records_end > dbf.len() {
+        return Err(malformed(
+            ".dbf",
+            format!("ends after {} bytes but more are needed", dbf.len()),
+        ));
+    }
+
+    let mut records = Vec::with_capacity(num_records);
+    for (i, record_bytes) in dbf[header_length..records_end]
+        .chunks_exact(record_length)
+        .enumerate()
+    {
+        let mut position = 1;
+        let mut record = Attributes::new();
+        for field in &fields {
+            let bytes = &record_bytes[position..position + field.length];
+            let raw = String::from_utf8_lossy(bytes);
+            let text = raw.trim_matches([' ', '\0']);
+            let value = match field.kind {
+                b'N' | b'F' if text.is_empty() || text.chars().all(|c| c == '*') => Attribute::Null,
+                b'N' | b'F' => match text.parse() {
+                    Ok(number) => Attribute::Number(number),
+                    Err(_) => {
+                        return Err(malformed(
+                            ".dbf",
+                            format!(
+                                "field {} of record {} is not a number: {}",
+                                field.name, i, text
+                            ),
+                        ));
+                    }
+                },
+                b'L' => match text {
+                    "T" | "t" | "Y" | "y" => Attribute::Logical(true),
+                    "F" | "f" | "N" | "n" => Attribute::Logical(false),
+                    _ => Attribute::Null,
+                },
+                b'D' if text.is_empty() => Attribute::Null,
+                b'D' => Attribute::Date(text.to_string()),
+                // text is left-aligned and padded with spaces
+                _ => Attribute::Text(raw.trim_end_matches([' ', '\0']).to_string()),
+            };
+            record.insert(field.name.clone(), value);
+            position += field.length;
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
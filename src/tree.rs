@@ -1,10 +1,24 @@
-#[cfg(feature = "pyo3")]
-use pyo3::prelude::*;
 #[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
+use crate::builder::TreeBuilder;
+use crate::bulk_loading;
+use crate::bulk_loading::BulkLoading;
 use crate::distance;
+use crate::distance::KNearest;
+use crate::distance::NearestEdge;
+use crate::edit;
+use crate::edit::Bounds;
+use crate::error::{BuildError, LoadError, QueryError};
 use crate::intersections;
+use crate::intersections::{Crossings, FillRule, Location};
+use crate::polygon;
+use crate::polygon::{Boundary, MultiPolygon, Ring};
+use crate::predicates::Precision;
+use crate::serialize;
+use crate::weighting::{Additive, Weighting};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 
 // a polygon point
 // x and y are coordinates
@@ -19,94 +33,948 @@ pub struct Point {
 }
 
 // edge connects two points
-#[derive(Debug, Clone)]
+// polygon is the index of the polygon the edge belongs to
+// interior is true for edges of holes
+#[derive(Debug, Clone, Copy)]
 pub struct Edge {
     pub p1: Point,
     pub p2: Point,
+    pub polygon: usize,
+    pub interior: bool,
+}
+
+// all edges of the tree in struct-of-arrays form so that the distance
+// computations only touch the coordinates they need
+// edges of one leaf node are stored next to each other
+#[derive(Debug, Clone, Default)]
+pub struct Edges {
+    pub x1: Vec<f64>,
+    pub y1: Vec<f64>,
+    pub h1: Vec<f64>,
+    pub x2: Vec<f64>,
+    pub y2: Vec<f64>,
+    pub h2: Vec<f64>,
+    pub index1: Vec<usize>,
+    pub index2: Vec<usize>,
+    pub in_between1: Vec<bool>,
+    pub in_between2: Vec<bool>,
+    pub polygon: Vec<usize>,
+    pub interior: Vec<bool>,
+}
+
+impl Edges {
+    pub fn len(&self) -> usize {
+        self.x1.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x1.is_empty()
+    }
+
+    // polygon is 0 if the tree does not store polygon ids
+    pub fn get(&self, i: usize) -> Edge {
+        Edge {
+            p1: Point {
+                x: self.x1[i],
+                y: self.y1[i],
+                h: self.h1[i],
+                index: self.index1[i],
+                in_between: self.in_between1[i],
+            },
+            p2: Point {
+                x: self.x2[i],
+                y: self.y2[i],
+                h: self.h2[i],
+                index: self.index2[i],
+                in_between: self.in_between2[i],
+            },
+            polygon: self.polygon.get(i).copied().unwrap_or(0),
+            interior: self.interior[i],
+        }
+    }
+
+    // (i, x1, y1, x2, y2) for the edges in range
+    // iterating over slices avoids bounds checks in the innermost loops
+    pub fn segments(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (usize, f64, f64, f64, f64)> + '_ {
+        let x1 = &self.x1[range.clone()];
+        let y1 = &self.y1[range.clone()];
+        let x2 = &self.x2[range.clone()];
+        let y2 = &self.y2[range.clone()];
+        range
+            .zip(x1.iter().zip(y1).zip(x2).zip(y2))
+            .map(|(i, (((&x1, &y1), &x2), &y2))| (i, x1, y1, x2, y2))
+    }
+
+    // (i, x, y, h) for the start points of the edges in range
+    pub fn start_points(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (usize, f64, f64, f64)> + '_ {
+        let x = &self.x1[range.clone()];
+        let y = &self.y1[range.clone()];
+        let h = &self.h1[range.clone()];
+        range
+            .zip(x.iter().zip(y).zip(h))
+            .map(|(i, ((&x, &y), &h))| (i, x, y, h))
+    }
+
+    pub fn push(&mut self, edge: &Edge) {
+        self.x1.push(edge.p1.x);
+        self.y1.push(edge.p1.y);
+        self.h1.push(edge.p1.h);
+        self.x2.push(edge.p2.x);
+        self.y2.push(edge.p2.y);
+        self.h2.push(edge.p2.h);
+        self.index1.push(edge.p1.index);
+        self.index2.push(edge.p2.index);
+        self.in_between1.push(edge.p1.in_between);
+        self.in_between2.push(edge.p2.in_between);
+        self.polygon.push(edge.polygon);
+        self.interior.push(edge.interior);
+    }
+
+    fn memory_usage(&self) -> usize {
+        let floats = [&self.x1, &self.y1, &self.h1, &self.x2, &self.y2, &self.h2];
+        let indices = [&self.index1, &self.index2, &self.polygon];
+        let flags = [&self.in_between1, &self.in_between2, &self.interior];
+
+        floats
+            .iter()
+            .map(|v| v.capacity() * size_of::<f64>())
+            .sum::<usize>()
+            + indices
+                .iter()
+                .map(|v| v.capacity() * size_of::<usize>())
+                .sum::<usize>()
+            + flags
+                .iter()
+                .map(|v| v.capacity() * size_of::<bool>())
+                .sum::<usize>()
+    }
 }
 
 // node is a box which has dimensions
-// it contains either other nodes
-// or it contains edges
-#[cfg_attr(feature = "pyo3", pyclass)]
-#[derive(Clone)]
+// it contains either other nodes or edges which are given as
+// ranges into Tree::nodes and Tree::edges
+#[derive(Debug, Clone)]
 pub struct Node {
     pub xmin: f64,
     pub xmax: f64,
     pub ymin: f64,
     pub ymax: f64,
     pub hmin: f64,
-    pub children_nodes: Vec<Node>,
-    pub edges: Vec<Edge>,
+    pub children_nodes: Range<usize>,
+    pub edges: Range<usize>,
+}
+
+// the search tree over all polygons
+// all nodes live in one array: the root comes first and the children of a node
+// are stored next to each other after their parent, nodes is empty if there
+// are no polygons
+// precision is used by all queries which check on which side of an edge a point is
+// fill_rule and tolerance are used by the queries which check whether points are inside
+// without polygon_ids the polygon column of edges is empty
+// polylines is true once the tree contains open polylines, then the queries
+// which check whether points are inside fail
+// payloads holds a value of the caller for some or all polygons, it is not
+// saved by to_bytes
+// first_vertices maps the index of the first vertex of each polygon to the
+// polygon so that vertex indices in query results lead back to their polygon
+// (if ids are stored), removed polygons keep their entry with None
+// the remaining fields are only needed to insert and remove polygons:
+// num_polygons and num_vertices are the next polygon id and vertex index,
+// polygon_bounds has the box of every polygon in the tree and also tells
+// which ids exist,
+// garbage_nodes and garbage_edges count entries which are no longer used,
+// and compact is false once an edit changed the order of the nodes
+#[derive(Debug, Clone)]
+pub struct Tree<T = ()> {
+    pub(crate) nodes: Vec<Node>,
+    pub(crate) edges: Edges,
+    pub(crate) precision: Precision,
+    pub(crate) fill_rule: FillRule,
+    pub(crate) tolerance: f64,
+    pub(crate) polygon_ids: bool,
+    pub(crate) polylines: bool,
+    pub(crate) bulk_loading: BulkLoading,
+    pub(crate) payloads: HashMap<usize, T>,
+    pub(crate) first_vertices: BTreeMap<usize, Option<usize>>,
+    pub(crate) num_edges_children: usize,
+    pub(crate) num_nodes_children: usize,
+    pub(crate) num_polygons: usize,
+    pub(crate) num_vertices: usize,
+    pub(crate) polygon_bounds: HashMap<usize, Bounds>,
+    pub(crate) garbage_nodes: usize,
+    pub(crate) garbage_edges: usize,
+    pub(crate) compact: bool,
 }
 
-pub type Tree = Vec<Node>;
+impl Default for Tree {
+    fn default() -> Tree {
+        let builder = TreeBuilder::default();
+
+        Tree {
+            nodes: Vec::new(),
+            edges: Edges::default(),
+            precision: builder.precision,
+            fill_rule: builder.fill_rule,
+            tolerance: builder.tolerance,
+            polygon_ids: builder.store_polygon_ids,
+            polylines: false,
+            bulk_loading: builder.bulk_loading,
+            payloads: HashMap::new(),
+            first_vertices: BTreeMap::new(),
+            num_edges_children: builder.num_edges_children,
+            num_nodes_children: builder.num_nodes_children,
+            num_polygons: 0,
+            num_vertices: 0,
+            polygon_bounds: HashMap::new(),
+            garbage_nodes: 0,
+            garbage_edges: 0,
+            compact: true,
+        }
+    }
+}
+
+impl<T> Tree<T> {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    pub fn set_precision(&mut self, precision: Precision) {
+        self.precision = precision;
+    }
+
+    pub fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    pub fn has_polygon_ids(&self) -> bool {
+        self.polygon_ids
+    }
+
+    pub fn has_polylines(&self) -> bool {
+        self.polylines
+    }
+
+    // number of polygon ids handed out so far, including removed polygons
+    pub fn num_polygons(&self) -> usize {
+        self.num_polygons
+    }
+
+    // number of vertex indices handed out so far, including those of removed polygons
+    pub fn num_vertices(&self) -> usize {
+        self.num_vertices
+    }
+
+    // adds a polygon to the tree and returns its id
+    // the new polygon gets the id num_polygons() and its vertices are numbered
+    // starting at num_vertices(), ids and indices of other polygons do not change
+    pub fn insert_polygon(&mut self, points: Vec<(f64, f64)>) -> Result<usize, BuildError> {
+        self.insert_polygon_h(polygon::pad(points))
+    }
+
+    pub fn insert_polygon_h(&mut self, points: Vec<(f64, f64, f64)>) -> Result<usize, BuildError> {
+        edit::insert_rings(self, polygon::rings_from_polygons(vec![points]))
+    }
+
+    pub fn insert_multipolygon(&mut self, multipolygon: MultiPolygon) -> Result<usize, BuildError> {
+        edit::insert_rings(self, polygon::rings_from_multipolygons(vec![multipolygon]))
+    }
+
+    // polylines share their ids with polygons
+    pub fn insert_polyline(&mut self, points: Vec<(f64, f64)>) -> Result<usize, BuildError> {
+        edit::insert_rings(
+            self,
+            polygon::rings_from_polylines(vec![polygon::pad(points)], 0),
+        )
+    }
+
+    // removes all rings of a polygon and its payload, ids and vertex indices
+    // are not reused
+    pub fn remove_polygon(&mut self, polygon: usize) -> Result<(), QueryError> {
+        edit::remove_polygon(self, polygon)
+    }
+
+    pub fn payload(&self, polygon: usize) -> Option<&T> {
+        self.payloads.get(&polygon)
+    }
+
+    // (polygon, payload) for all polygons which have a payload
+    pub fn payloads(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.payloads
+            .iter()
+            .map(|(&polygon, payload)| (polygon, payload))
+    }
+
+    // attaches a payload to a polygon of the tree and returns the one it
+    // replaces
+    pub fn set_payload(&mut self, polygon: usize, payload: T) -> Result<Option<T>, QueryError> {
+        if !self.contains_polygon(polygon) {
+            return Err(QueryError::UnknownPolygon { polygon });
+        }
+        Ok(self.payloads.insert(polygon, payload))
+    }
+
+    // the same tree with payloads[i] attached to polygon i
+    // payloads of polygons which are not in the tree are dropped
+    pub fn with_payloads<U>(self, payloads: impl IntoIterator<Item = U>) -> Tree<U> {
+        let mut tree = Tree {
+            nodes: self.nodes,
+            edges: self.edges,
+            precision: self.precision,
+            fill_rule: self.fill_rule,
+            tolerance: self.tolerance,
+            polygon_ids: self.polygon_ids,
+            polylines: self.polylines,
+            bulk_loading: self.bulk_loading,
+            payloads: HashMap::new(),
+            first_vertices: self.first_vertices,
+            num_edges_children: self.num_edges_children,
+            num_nodes_children: self.num_nodes_children,
+            num_polygons: self.num_polygons,
+            num_vertices: self.num_vertices,
+            polygon_bounds: self.polygon_bounds,
+            garbage_nodes: self.garbage_nodes,
+            garbage_edges: self.garbage_edges,
+            compact: self.compact,
+        };
+        for (polygon, payload) in payloads.into_iter().enumerate() {
+            if tree.contains_polygon(polygon) {
+                tree.payloads.insert(polygon, payload);
+            }
+        }
+        tree
+    }
+
+    // polygon which owns a vertex index (or an edge given by the index of its
+    // start point) as returned by the queries
+    // None if the tree was built without polygon ids or the polygon was removed
+    pub fn vertex_polygon(&self, index: usize) -> Option<usize> {
+        if index >= self.num_vertices {
+            return None;
+        }
+        self.first_vertices
+            .range(..=index)
+            .next_back()
+            .and_then(|(_, &polygon)| polygon)
+    }
+
+    pub fn vertex_payload(&self, index: usize) -> Option<&T> {
+        self.vertex_polygon(index)
+            .and_then(|polygon| self.payload(polygon))
+    }
+
+    // polygons without rings (null or empty geometries) are not in the tree
+    fn contains_polygon(&self, polygon: usize) -> bool {
+        self.polygon_bounds.contains_key(&polygon)
+    }
+
+    // compact binary representation which can be loaded with from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.compact {
+            serialize::tree_to_bytes(self)
+        } else {
+            let mut tree = self.clone_without_payloads();
+            edit::compact(&mut tree);
+            serialize::tree_to_bytes(&tree)
+        }
+    }
+
+    // number of bytes used by the tree including the node and edge arrays
+    // but not memory the payloads point to
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Tree<T>>()
+            + self.nodes.capacity() * size_of::<Node>()
+            + self.edges.memory_usage()
+            + self.polygon_bounds.capacity() * size_of::<(usize, Bounds)>()
+            + self.payloads.capacity() * size_of::<(usize, T)>()
+            + self.first_vertices.len() * size_of::<(usize, Option<usize>)>()
+    }
+
+    // payloads need not be cloneable
+    fn clone_without_payloads(&self) -> Tree {
+        Tree {
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
+            precision: self.precision,
+            fill_rule: self.fill_rule,
+            tolerance: self.tolerance,
+            polygon_ids: self.polygon_ids,
+            polylines: self.polylines,
+            bulk_loading: self.bulk_loading,
+            payloads: HashMap::new(),
+            first_vertices: self.first_vertices.clone(),
+            num_edges_children: self.num_edges_children,
+            num_nodes_children: self.num_nodes_children,
+            num_polygons: self.num_polygons,
+            num_vertices: self.num_vertices,
+            polygon_bounds: self.polygon_bounds.clone(),
+            garbage_nodes: self.garbage_nodes,
+            garbage_edges: self.garbage_edges,
+            compact: self.compact,
+        }
+    }
+
+    pub(crate) fn root(&self) -> Option<&Node> {
+        self.nodes.first()
+    }
+
+    pub(crate) fn children(&self, node: &Node) -> &[Node] {
+        &self.nodes[node.children_nodes.clone()]
+    }
+}
+
+impl Tree {
+    // the loaded tree has no payloads
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tree, LoadError> {
+        serialize::tree_from_bytes(bytes)
+    }
+}
 
 impl Node {
-    fn adjust_bounds(&mut self, xmin: f64, xmax: f64, ymin: f64, ymax: f64, hmin: f64) {
+    pub(crate) fn new() -> Node {
+        let large_number = f64::MAX;
+
+        Node {
+            xmin: large_number,
+            xmax: -large_number,
+            ymin: large_number,
+            ymax: -large_number,
+            hmin: large_number,
+            children_nodes: 0..0,
+            edges: 0..0,
+        }
+    }
+
+    pub(crate) fn adjust_bounds(&mut self, xmin: f64, xmax: f64, ymin: f64, ymax: f64, hmin: f64) {
         self.xmin = self.xmin.min(xmin);
         self.xmax = self.xmax.max(xmax);
         self.ymin = self.ymin.min(ymin);
         self.ymax = self.ymax.max(ymax);
         self.hmin = self.hmin.min(hmin);
     }
-    fn insert_node(&mut self, new_node: Node) {
-        let boxed_node = Box::new(new_node);
-        self.children_nodes.push(*boxed_node);
-    }
-    fn insert_edge(&mut self, new_edge: Edge) {
-        self.edges.push(new_edge);
-    }
 }
 
-#[cfg_attr(feature = "pyo3", pyfunction)]
 pub fn build_search_tree(
     polygons: Vec<Vec<(f64, f64)>>,
     num_edges_children: usize,
     num_nodes_children: usize,
-) -> Vec<Node> {
-    let polygons_h = pad(polygons);
-    build_search_tree_h(polygons_h, num_edges_children, num_nodes_children)
+) -> Tree {
+    match try_build_search_tree(polygons, num_edges_children, num_nodes_children) {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
 }
 
-#[cfg_attr(feature = "pyo3", pyfunction)]
 pub fn build_search_tree_h(
     polygons: Vec<Vec<(f64, f64, f64)>>,
     num_edges_children: usize,
     num_nodes_children: usize,
-) -> Vec<Node> {
-    let mut nodes = Vec::new();
+) -> Tree {
+    match try_build_search_tree_h(polygons, num_edges_children, num_nodes_children) {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+// each multipolygon may consist of several polygons with holes
+// query results refer to the index of the multipolygon
+pub fn build_search_tree_multipolygons(
+    multipolygons: Vec<MultiPolygon>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Tree {
+    match try_build_search_tree_multipolygons(multipolygons, num_edges_children, num_nodes_children)
+    {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+// edges connect consecutive points but not the last point with the first one
+// only distances and nearest vertices and edges can be computed for such a tree
+pub fn build_search_tree_polylines(
+    polylines: Vec<Vec<(f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Tree {
+    match try_build_search_tree_polylines(polylines, num_edges_children, num_nodes_children) {
+        Ok(tree) => tree,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_build_search_tree(
+    polygons: Vec<Vec<(f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<Tree, BuildError> {
+    TreeBuilder::new()
+        .num_edges_children(num_edges_children)
+        .num_nodes_children(num_nodes_children)
+        .build(polygons)
+}
+
+pub fn try_build_search_tree_h(
+    polygons: Vec<Vec<(f64, f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<Tree, BuildError> {
+    TreeBuilder::new()
+        .num_edges_children(num_edges_children)
+        .num_nodes_children(num_nodes_children)
+        .build_h(polygons)
+}
+
+pub fn try_build_search_tree_polylines(
+    polylines: Vec<Vec<(f64, f64)>>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<Tree, BuildError> {
+    TreeBuilder::new()
+        .num_edges_children(num_edges_children)
+        .num_nodes_children(num_nodes_children)
+        .build_polylines(polylines)
+}
+
+pub fn try_build_search_tree_multipolygons(
+    multipolygons: Vec<MultiPolygon>,
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<Tree, BuildError> {
+    TreeBuilder::new()
+        .num_edges_children(num_edges_children)
+        .num_nodes_children(num_nodes_children)
+        .build_multipolygons(multipolygons)
+}
+
+// num_polygons is the number of input (multi)polygons
+pub fn build_tree(
+    rings: Vec<Ring>,
+    num_polygons: usize,
+    builder: &TreeBuilder,
+) -> Result<Tree, BuildError> {
+    let num_edges_children = builder.num_edges_children;
+    let num_nodes_children = builder.num_nodes_children;
+
+    check_input(&rings, num_edges_children, num_nodes_children)?;
+
+    if !(builder.tolerance >= 0.0 && builder.tolerance.is_finite()) {
+        return Err(BuildError::InvalidTolerance {
+            value: builder.tolerance,
+        });
+    }
+
+    let mut edges = Edges::default();
+    let mut leaves = Vec::new();
 
     let mut offset = 0;
-    for polygon in polygons {
+    for ring in &rings {
         // group edges to nodes, num_edges_children at the time
-        nodes.append(&mut group_edges(
+        leaves.append(&mut group_edges(
             num_edges_children,
-            points_to_edges(&polygon, offset),
+            points_to_edges(ring, offset),
+            &mut edges,
         ));
 
-        offset += polygon.len();
+        offset += ring.num_input_points;
     }
 
-    // we group nodes into a tree
-    while nodes.len() > 1 {
-        nodes = group_nodes(num_nodes_children, nodes);
+    let mut levels = build_levels(leaves, num_nodes_children, builder.bulk_loading);
+
+    // sorting the leaves scattered their edges and we store them again in
+    // leaf order so that neighboring leaves stay close in memory
+    if builder.bulk_loading != BulkLoading::InputOrder {
+        edges = reorder_edges(&mut levels[0], &edges);
+    }
+
+    let polygon_bounds = edit::polygon_bounds_from_rings(&rings);
+    let first_vertices = if builder.store_polygon_ids {
+        edit::first_vertices_from_rings(&rings, 0)
+    } else {
+        edges.polygon = Vec::new();
+        BTreeMap::new()
+    };
+
+    Ok(Tree {
+        nodes: flatten_levels(levels),
+        edges,
+        precision: builder.precision,
+        fill_rule: builder.fill_rule,
+        tolerance: builder.tolerance,
+        polygon_ids: builder.store_polygon_ids,
+        polylines: rings.iter().any(|ring| !ring.closed),
+        bulk_loading: builder.bulk_loading,
+        payloads: HashMap::new(),
+        first_vertices,
+        num_edges_children,
+        num_nodes_children,
+        num_polygons,
+        num_vertices: offset,
+        polygon_bounds,
+        garbage_nodes: 0,
+        garbage_edges: 0,
+        compact: true,
+    })
+}
+
+// we group nodes into a tree, one level at the time
+// levels[0] holds the leaves and the last level the root
+pub(crate) fn build_levels(
+    leaves: Vec<Node>,
+    num_nodes_children: usize,
+    bulk_loading: BulkLoading,
+) -> Vec<Vec<Node>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let leaf_level = levels.len() == 1;
+        let level = levels.last_mut().unwrap();
+        bulk_loading::sort_nodes(level, num_nodes_children, bulk_loading, leaf_level);
+        let parents = group_nodes(num_nodes_children, level);
+        levels.push(parents);
+    }
+    levels
+}
+
+fn reorder_edges(leaves: &mut [Node], edges: &Edges) -> Edges {
+    let mut reordered = Edges::default();
+    for leaf in leaves {
+        let first = reordered.len();
+        for i in leaf.edges.clone() {
+            reordered.push(&edges.get(i));
+        }
+        leaf.edges = first..reordered.len();
+    }
+    reordered
+}
+
+// places the levels into one array, from the root down to the leaves, and
+// shifts the children ranges which so far pointed into the level below
+pub(crate) fn flatten_levels(levels: Vec<Vec<Node>>) -> Vec<Node> {
+    let mut nodes = Vec::with_capacity(levels.iter().map(|level| level.len()).sum());
+
+    for (j, level) in levels.into_iter().enumerate().rev() {
+        let children_offset = nodes.len() + level.len();
+        for mut node in level {
+            if j > 0 {
+                node.children_nodes = (node.children_nodes.start + children_offset)
+                    ..(node.children_nodes.end + children_offset);
+            }
+            nodes.push(node);
+        }
     }
 
     nodes
 }
 
-fn pad(input: Vec<Vec<(f64, f64)>>) -> Vec<Vec<(f64, f64, f64)>> {
-    let mut output = Vec::new();
-    for v in input {
-        output.push(v.iter().map(|(x, y)| (*x, *y, 0.0)).collect());
+// we check everything that would otherwise make the tree construction panic
+// or produce a tree which gives meaningless results
+pub(crate) fn check_input(
+    rings: &[Ring],
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<(), BuildError> {
+    check_fan_out(num_edges_children, num_nodes_children)?;
+
+    for ring in rings {
+        if ring.closed && ring.points.len() < 3 {
+            return Err(BuildError::DegeneratePolygon {
+                polygon: ring.polygon,
+                ring: ring.ring,
+                num_points: ring.points.len(),
+            });
+        }
+        if !ring.closed && ring.points.len() < 2 {
+            return Err(BuildError::DegeneratePolyline {
+                polyline: ring.polygon,
+                num_points: ring.points.len(),
+            });
+        }
+        if let Some(j) = ring
+            .points
+            .iter()
+            .position(|(x, y, h)| !(x.is_finite() && y.is_finite() && h.is_finite()))
+        {
+            return Err(BuildError::NonFiniteCoordinate {
+                polygon: ring.polygon,
+                ring: ring.ring,
+                point: ring.positions[j],
+            });
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn check_fan_out(
+    num_edges_children: usize,
+    num_nodes_children: usize,
+) -> Result<(), BuildError> {
+    if num_edges_children < 1 {
+        return Err(BuildError::InvalidFanOut {
+            parameter: "num_edges_children",
+            value: num_edges_children,
+        });
+    }
+
+    // with one child per node, grouping nodes would never reach a single root
+    if num_nodes_children < 2 {
+        return Err(BuildError::InvalidFanOut {
+            parameter: "num_nodes_children",
+            value: num_nodes_children,
+        });
+    }
+
+    Ok(())
+}
+
+// uses the fill rule the tree was built with
+// panics if the tree contains open polylines
+pub fn points_are_inside<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<bool> {
+    points_are_inside_with_fill_rule(tree, points, tree.fill_rule)
+}
+
+pub fn try_points_are_inside<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<bool>, QueryError> {
+    try_points_are_inside_with_fill_rule(tree, points, tree.fill_rule)
+}
+
+// with FillRule::EvenOdd a point is inside if the number of intersections to reach
+// the point "from left" is impair, with FillRule::NonZero a point is inside if the
+// edges wind around it
+// if the tree was built with a tolerance, points within the tolerance of an edge
+// are inside
+pub fn points_are_inside_with_fill_rule<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    fill_rule: FillRule,
+) -> Vec<bool> {
+    match try_points_are_inside_with_fill_rule(tree, points, fill_rule) {
+        Ok(inside) => inside,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_points_are_inside_with_fill_rule<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    fill_rule: FillRule,
+) -> Result<Vec<bool>, QueryError> {
+    if tree.polylines {
+        return Err(QueryError::OpenPolylines);
+    }
+
+    let Some(root) = tree.root() else {
+        return Ok(vec![false; points.len()]);
+    };
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    Ok(iter
+        .map(|p| {
+            if tree.tolerance > 0.0 {
+                let mut c = Crossings::default();
+                distance::get_boundary_or_intersections(
+                    tree,
+                    root,
+                    tree.tolerance,
+                    &mut c,
+                    *p,
+                    tree.precision,
+                ) || fill_rule.is_inside(c)
+            } else {
+                fill_rule.is_inside(intersections::num_intersections(
+                    tree,
+                    root,
+                    Crossings::default(),
+                    *p,
+                    tree.precision,
+                ))
+            }
+        })
+        .collect())
+}
+
+// points within tolerance of an edge are on the boundary, with tolerance 0.0
+// only points exactly on an edge are
+// panics if the tree contains open polylines
+pub fn classify_points<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    tolerance: f64,
+) -> Vec<Location> {
+    match try_classify_points(tree, points, tolerance) {
+        Ok(locations) => locations,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_classify_points<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    tolerance: f64,
+) -> Result<Vec<Location>, QueryError> {
+    if tree.polylines {
+        return Err(QueryError::OpenPolylines);
+    }
+
+    let Some(root) = tree.root() else {
+        return Ok(vec![Location::Outside; points.len()]);
+    };
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    Ok(iter
+        .map(|p| {
+            let mut c = Crossings::default();
+            if distance::get_boundary_or_intersections(
+                tree,
+                root,
+                tolerance,
+                &mut c,
+                *p,
+                tree.precision,
+            ) {
+                Location::OnBoundary
+            } else if tree.fill_rule.is_inside(c) {
+                Location::Inside
+            } else {
+                Location::Outside
+            }
+        })
+        .collect())
+}
+
+// index of the polygon containing each point or None if the point is outside
+// all polygons, for overlapping polygons this is the smallest index
+// panics if the tree was built without polygon ids or contains open polylines
+pub fn containing_polygons<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<Option<usize>> {
+    match try_containing_polygons(tree, points) {
+        Ok(polygons) => polygons,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+// payload of the polygon containing each point, None if the point is outside
+// all polygons or the polygon has no payload
+// panics if the tree was built without polygon ids or contains open polylines
+pub fn containing_payloads<'a, T: Sync>(
+    tree: &'a Tree<T>,
+    points: &[(f64, f64)],
+) -> Vec<Option<&'a T>> {
+    match try_containing_payloads(tree, points) {
+        Ok(payloads) => payloads,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+// sorted indices of all polygons containing each point
+// panics if the tree was built without polygon ids or contains open polylines
+pub fn all_containing_polygons<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<Vec<usize>> {
+    match try_all_containing_polygons(tree, points) {
+        Ok(polygons) => polygons,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_containing_polygons<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<Option<usize>>, QueryError> {
+    Ok(try_all_containing_polygons(tree, points)?
+        .into_iter()
+        .map(|polygons| polygons.first().copied())
+        .collect())
+}
+
+pub fn try_containing_payloads<'a, T: Sync>(
+    tree: &'a Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<Option<&'a T>>, QueryError> {
+    let polygons = try_containing_polygons(tree, points)?;
+    Ok(polygons
+        .into_iter()
+        .map(|polygon| polygon.and_then(|polygon| tree.payload(polygon)))
+        .collect())
+}
+
+pub fn try_all_containing_polygons<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<Vec<usize>>, QueryError> {
+    if !tree.polygon_ids {
+        return Err(QueryError::MissingPolygonIds);
     }
-    output
+    if tree.polylines {
+        return Err(QueryError::OpenPolylines);
+    }
+
+    let Some(root) = tree.root() else {
+        return Ok(vec![Vec::new(); points.len()]);
+    };
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    let polygons = iter
+        .map(|p| {
+            let mut counts = Vec::new();
+            intersections::polygon_intersections(tree, root, *p, tree.precision, &mut counts);
+
+            // same parity rule as in points_are_inside but for each polygon
+            let mut polygons: Vec<usize> = counts
+                .into_iter()
+                .filter(|(_, c)| tree.fill_rule.is_inside(*c))
+                .map(|(polygon, _)| polygon)
+                .collect();
+            polygons.sort_unstable();
+            polygons
+        })
+        .collect();
+
+    Ok(polygons)
 }
 
-pub fn points_are_inside(tree: &Tree, points: &[(f64, f64)]) -> Vec<bool> {
-    // point is inside some polygon if the number of intersections to reach
-    // the point "from left" is impair
+pub fn distances_nearest_edges<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<f64> {
+    distances_nearest_edges_with_boundary(tree, points, Boundary::All)
+}
+
+// distances to the nearest edges of only exterior rings or only holes
+pub fn distances_nearest_edges_with_boundary<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    boundary: Boundary,
+) -> Vec<f64> {
+    let Some(root) = tree.root() else {
+        return vec![f64::INFINITY; points.len()];
+    };
+
+    let large_number = f64::MAX;
 
     #[cfg(feature = "rayon")]
     let iter = points.par_iter();
@@ -114,14 +982,19 @@ pub fn points_are_inside(tree: &Tree, points: &[(f64, f64)]) -> Vec<bool> {
     #[cfg(not(feature = "rayon"))]
     let iter = points.iter();
 
-    // the division by 2 is because we count each edge intersection twice
-    // and the reason for that is that it makes it easier to deal with the case
-    // where the point has the same y coordinate as an edge point
-    iter.map(|p| ((intersections::num_intersections(&tree[0], 0, *p) / 2) % 2) != 0)
+    iter.map(|p| distance::get_distance_edge(tree, root, large_number, *p, boundary))
         .collect()
 }
 
-pub fn distances_nearest_edges(tree: &Tree, points: &[(f64, f64)]) -> Vec<f64> {
+// distances to the nearest edges where h is interpolated linearly along each
+// edge and added to the distance, so that the buffer around the polygons can
+// vary without adding vertices
+// with h = 0 everywhere this is the same as distances_nearest_edges
+pub fn distances_nearest_edges_h<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<f64> {
+    let Some(root) = tree.root() else {
+        return vec![f64::INFINITY; points.len()];
+    };
+
     let large_number = f64::MAX;
 
     #[cfg(feature = "rayon")]
@@ -130,11 +1003,32 @@ pub fn distances_nearest_edges(tree: &Tree, points: &[(f64, f64)]) -> Vec<f64> {
     #[cfg(not(feature = "rayon"))]
     let iter = points.iter();
 
-    iter.map(|p| distance::get_distance_edge(&tree[0], large_number, *p))
+    iter.map(|p| distance::get_distance_edge_h(tree, root, large_number, *p))
         .collect()
 }
 
-pub fn distances_nearest_vertices(tree: &Tree, points: &[(f64, f64)]) -> (Vec<usize>, Vec<f64>) {
+// distances to the nearest edges which are negative for points inside
+// and positive for points outside
+// panics if the tree contains open polylines
+pub fn signed_distances<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<f64> {
+    match try_signed_distances(tree, points) {
+        Ok(distances) => distances,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_signed_distances<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<f64>, QueryError> {
+    if tree.polylines {
+        return Err(QueryError::OpenPolylines);
+    }
+
+    let Some(root) = tree.root() else {
+        return Ok(vec![f64::INFINITY; points.len()]);
+    };
+
     let large_number = f64::MAX;
 
     #[cfg(feature = "rayon")]
@@ -143,8 +1037,83 @@ pub fn distances_nearest_vertices(tree: &Tree, points: &[(f64, f64)]) -> (Vec<us
     #[cfg(not(feature = "rayon"))]
     let iter = points.iter();
 
-    let tuples: Vec<(usize, f64)> = iter
-        .map(|p| distance::get_distance_vertex(&tree[0], 0, large_number, *p))
+    Ok(iter
+        .map(|p| {
+            let (d, c) = distance::get_distance_edge_and_intersections(
+                tree,
+                root,
+                large_number,
+                Crossings::default(),
+                *p,
+                tree.precision,
+            );
+            // same fill rule as in points_are_inside
+            if tree.fill_rule.is_inside(c) { -d } else { d }
+        })
+        .collect())
+}
+
+// nearest edge for each point, None only if the tree has no polygons
+// panics if the tree was built without polygon ids
+pub fn nearest_edges<T: Sync>(tree: &Tree<T>, points: &[(f64, f64)]) -> Vec<Option<NearestEdge>> {
+    match try_nearest_edges(tree, points) {
+        Ok(nearest) => nearest,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+pub fn try_nearest_edges<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> Result<Vec<Option<NearestEdge>>, QueryError> {
+    if !tree.polygon_ids {
+        return Err(QueryError::MissingPolygonIds);
+    }
+
+    let Some(root) = tree.root() else {
+        return Ok(vec![None; points.len()]);
+    };
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    Ok(iter
+        .map(|p| distance::get_nearest_edge(tree, root, None, *p))
+        .collect())
+}
+
+// the index is None only if the tree has no polygons
+pub fn distances_nearest_vertices<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+) -> (Vec<Option<usize>>, Vec<f64>) {
+    distances_nearest_vertices_weighted(tree, points, &Additive)
+}
+
+// the distances combine the distance to each vertex with its h as given
+// by weighting, distances_nearest_vertices uses Additive
+pub fn distances_nearest_vertices_weighted<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    weighting: &(impl Weighting + Sync),
+) -> (Vec<Option<usize>>, Vec<f64>) {
+    let Some(root) = tree.root() else {
+        return (vec![None; points.len()], vec![f64::INFINITY; points.len()]);
+    };
+
+    let large_number = f64::MAX;
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    let tuples: Vec<(Option<usize>, f64)> = iter
+        .map(|p| distance::get_distance_vertex(tree, root, None, large_number, *p, weighting))
         .collect();
 
     let mut indices = Vec::new();
@@ -158,102 +1127,222 @@ pub fn distances_nearest_vertices(tree: &Tree, points: &[(f64, f64)]) -> (Vec<us
     (indices, distances)
 }
 
-fn group_nodes(num_nodes_children: usize, input: Vec<Node>) -> Vec<Node> {
-    let num_input = input.len();
-    let n = num_input / num_nodes_children;
-    let num_parents = match num_input % num_nodes_children {
-        0 => n,
-        _ => n + 1,
+// indices and distances of the k nearest vertices for each point
+// sorted by increasing distance
+pub fn k_nearest_vertices<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    k: usize,
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    k_nearest_vertices_weighted(tree, points, k, &Additive)
+}
+
+pub fn k_nearest_vertices_weighted<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    k: usize,
+    weighting: &(impl Weighting + Sync),
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    k_nearest_for_points(tree.root(), points, k, |root, nearest, p| {
+        distance::get_k_nearest_vertices(tree, root, nearest, p, weighting)
+    })
+}
+
+// edges are identified by the vertex index of their start point
+pub fn k_nearest_edges<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    k: usize,
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    k_nearest_for_points(tree.root(), points, k, |root, nearest, p| {
+        distance::get_k_nearest_edges(tree, root, nearest, p)
+    })
+}
+
+// runs one k nearest search from the root for each point
+// with k == 0 the heap would never fill up and nothing could be pruned, so
+// just like for an empty tree each point gets no results
+pub(crate) fn k_nearest_for_points(
+    root: Option<&Node>,
+    points: &[(f64, f64)],
+    k: usize,
+    search: impl Fn(&Node, &mut KNearest, (f64, f64)) + Sync,
+) -> (Vec<Vec<usize>>, Vec<Vec<f64>>) {
+    let Some(root) = root.filter(|_| k > 0) else {
+        return (
+            vec![Vec::new(); points.len()],
+            vec![Vec::new(); points.len()],
+        );
     };
 
-    let large_number = f64::MAX;
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
 
-    let mut parents: Vec<Node> = Vec::new();
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
 
-    let mut i = 0;
-    for _k in 0..num_parents {
-        let mut new_parent = Node {
-            xmin: large_number,
-            xmax: -large_number,
-            ymin: large_number,
-            ymax: -large_number,
-            hmin: large_number,
-            edges: Vec::new(),
-            children_nodes: Vec::new(),
-        };
-        for _l in 0..num_nodes_children {
-            if i < input.len() {
-                new_parent.adjust_bounds(
-                    input[i].xmin,
-                    input[i].xmax,
-                    input[i].ymin,
-                    input[i].ymax,
-                    input[i].hmin,
-                );
-                new_parent.insert_node(input[i].clone());
-                i += 1;
-            }
-        }
-        parents.push(new_parent);
-    }
+    let tuples: Vec<(Vec<usize>, Vec<f64>)> = iter
+        .map(|p| {
+            let mut nearest = KNearest::new(k);
+            search(root, &mut nearest, *p);
+            nearest.into_sorted()
+        })
+        .collect();
 
-    parents
+    tuples.into_iter().unzip()
 }
 
-fn group_edges(num_edges_children: usize, input: Vec<Edge>) -> Tree {
-    let num_input = input.len();
-    let n = num_input / num_edges_children;
-    let num_parents = match num_input % num_edges_children {
-        0 => n,
-        _ => n + 1,
+// all vertices within distance r of each point
+// the result is in compressed sparse row form: the sorted vertex indices
+// for point i are indices[offsets[i]..offsets[i + 1]]
+pub fn vertices_within<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    r: f64,
+) -> (Vec<usize>, Vec<usize>) {
+    let Some(root) = tree.root() else {
+        return (vec![0; points.len() + 1], Vec::new());
     };
 
-    let large_number = f64::MAX;
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
 
-    let mut parents = Vec::new();
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
 
-    let mut i = 0;
-    for _k in 0..num_parents {
-        let mut new_parent = Node {
-            xmin: large_number,
-            xmax: -large_number,
-            ymin: large_number,
-            ymax: -large_number,
-            hmin: large_number,
-            edges: Vec::new(),
-            children_nodes: Vec::new(),
-        };
-        for _l in 0..num_edges_children {
-            if i < input.len() {
-                new_parent.adjust_bounds(
-                    input[i].p1.x,
-                    input[i].p1.x,
-                    input[i].p1.y,
-                    input[i].p1.y,
-                    input[i].p1.h,
-                );
-                new_parent.adjust_bounds(
-                    input[i].p2.x,
-                    input[i].p2.x,
-                    input[i].p2.y,
-                    input[i].p2.y,
-                    input[i].p2.h,
-                );
-                new_parent.insert_edge(input[i].clone());
-                i += 1;
-            }
-        }
-        parents.push(new_parent);
+    let rows: Vec<Vec<usize>> = iter
+        .map(|p| {
+            let mut indices = Vec::new();
+            distance::get_vertices_within(tree, root, r, *p, &mut indices);
+            indices.sort_unstable();
+            indices.dedup();
+            indices
+        })
+        .collect();
+
+    compress_rows(rows)
+}
+
+// all edges within distance r of each point, in the same form as
+// vertices_within and with edges identified by the index of their start point
+pub fn edges_within<T: Sync>(
+    tree: &Tree<T>,
+    points: &[(f64, f64)],
+    r: f64,
+) -> (Vec<usize>, Vec<usize>) {
+    let Some(root) = tree.root() else {
+        return (vec![0; points.len() + 1], Vec::new());
+    };
+
+    #[cfg(feature = "rayon")]
+    let iter = points.par_iter();
+
+    #[cfg(not(feature = "rayon"))]
+    let iter = points.iter();
+
+    let rows: Vec<Vec<usize>> = iter
+        .map(|p| {
+            let mut indices = Vec::new();
+            distance::get_edges_within(tree, root, r, *p, &mut indices);
+            indices.sort_unstable();
+            indices
+        })
+        .collect();
+
+    compress_rows(rows)
+}
+
+fn compress_rows(rows: Vec<Vec<usize>>) -> (Vec<usize>, Vec<usize>) {
+    let mut offsets = Vec::with_capacity(rows.len() + 1);
+    offsets.push(0);
+    let mut indices = Vec::with_capacity(rows.iter().map(|row| row.len()).sum());
+    for row in rows {
+        indices.extend(row);
+        offsets.push(indices.len());
     }
+    (offsets, indices)
+}
 
-    parents
+// parents of num_nodes_children nodes each, their children ranges
+// are positions in input
+fn group_nodes(num_nodes_children: usize, input: &[Node]) -> Vec<Node> {
+    input
+        .chunks(num_nodes_children)
+        .enumerate()
+        .map(|(k, chunk)| {
+            let mut new_parent = Node::new();
+            for node in chunk {
+                new_parent.adjust_bounds(node.xmin, node.xmax, node.ymin, node.ymax, node.hmin);
+            }
+            let first = k * num_nodes_children;
+            new_parent.children_nodes = first..(first + chunk.len());
+            new_parent
+        })
+        .collect()
+}
+
+// leaves of num_edges_children edges each, the edges are appended to edges
+pub(crate) fn group_edges(
+    num_edges_children: usize,
+    input: Vec<Edge>,
+    edges: &mut Edges,
+) -> Vec<Node> {
+    input
+        .chunks(num_edges_children)
+        .map(|chunk| {
+            let mut new_parent = Node::new();
+            let first = edges.len();
+            for edge in chunk {
+                new_parent.adjust_bounds(edge.p1.x, edge.p1.x, edge.p1.y, edge.p1.y, edge.p1.h);
+                new_parent.adjust_bounds(edge.p2.x, edge.p2.x, edge.p2.y, edge.p2.y, edge.p2.h);
+                edges.push(edge);
+            }
+            new_parent.edges = first..edges.len();
+            new_parent
+        })
+        .collect()
 }
 
 fn is_in_between(y1: &f64, y2: &f64, y3: &f64) -> bool {
     (y1 < y2 && y2 < y3) || (y1 > y2 && y2 > y3)
 }
 
-fn points_to_edges(points: &[(f64, f64, f64)], offset: usize) -> Vec<Edge> {
+// consecutive points are connected but the last point not with the first one
+fn polyline_to_edges(ring: &Ring, offset: usize) -> Vec<Edge> {
+    let points = &ring.points;
+    let n = points.len();
+
+    // the end points have only one neighbor
+    let point = |k: usize| Point {
+        x: points[k].0,
+        y: points[k].1,
+        h: points[k].2,
+        index: offset + ring.positions[k],
+        in_between: k > 0
+            && k + 1 < n
+            && is_in_between(&points[k - 1].1, &points[k].1, &points[k + 1].1),
+    };
+
+    (0..n - 1)
+        .map(|k| Edge {
+            p1: point(k),
+            p2: point(k + 1),
+            polygon: ring.polygon,
+            interior: false,
+        })
+        .collect()
+}
+
+pub(crate) fn points_to_edges(ring: &Ring, offset: usize) -> Vec<Edge> {
+    if !ring.closed {
+        return polyline_to_edges(ring, offset);
+    }
+
+    let points = &ring.points;
+
+    // vertex indices refer to the ring as the caller gave it
+    let index = |k: usize| offset + ring.positions[k];
+
     let mut edges: Vec<Edge> = points
         .windows(4)
         .enumerate()
@@ -262,16 +1351,18 @@ fn points_to_edges(points: &[(f64, f64, f64)], offset: usize) -> Vec<Edge> {
                 x: t[1].0,
                 y: t[1].1,
                 h: t[1].2,
-                index: offset + i + 1,
+                index: index(i + 1),
                 in_between: is_in_between(&t[0].1, &t[1].1, &t[2].1),
             },
             p2: Point {
                 x: t[2].0,
                 y: t[2].1,
                 h: t[2].2,
-                index: offset + i + 2,
+                index: index(i + 2),
                 in_between: is_in_between(&t[1].1, &t[2].1, &t[3].1),
             },
+            polygon: ring.polygon,
+            interior: ring.interior,
         })
         .collect();
 
@@ -282,16 +1373,18 @@ fn points_to_edges(points: &[(f64, f64, f64)], offset: usize) -> Vec<Edge> {
             x: points[n - 1].0,
             y: points[n - 1].1,
             h: points[n - 1].2,
-            index: offset + n - 1,
+            index: index(n - 1),
             in_between: is_in_between(&points[n - 2].1, &points[n - 1].1, &points[n].1),
         },
         p2: Point {
             x: points[n].0,
             y: points[n].1,
             h: points[n].2,
-            index: offset + n,
+            index: index(n),
             in_between: is_in_between(&points[n - 1].1, &points[n].1, &points[0].1),
         },
+        polygon: ring.polygon,
+        interior: ring.interior,
     });
 
     edges.push(Edge {
@@ -299,16 +1392,18 @@ fn points_to_edges(points: &[(f64, f64, f64)], offset: usize) -> Vec<Edge> {
             x: points[n].0,
             y: points[n].1,
             h: points[n].2,
-            index: offset + n,
+            index: index(n),
             in_between: is_in_between(&points[n - 1].1, &points[n].1, &points[0].1),
         },
         p2: Point {
             x: points[0].0,
             y: points[0].1,
             h: points[0].2,
-            index: offset,
+            index: index(0),
             in_between: is_in_between(&points[n].1, &points[0].1, &points[1].1),
         },
+        polygon: ring.polygon,
+        interior: ring.interior,
     });
 
     edges.push(Edge {
@@ -316,16 +1411,18 @@ fn points_to_edges(points: &[(f64, f64, f64)], offset: usize) -> Vec<Edge> {
             x: points[0].0,
             y: points[0].1,
             h: points[0].2,
-            index: offset,
+            index: index(0),
             in_between: is_in_between(&points[n].1, &points[0].1, &points[1].1),
         },
         p2: Point {
             x: points[1].0,
             y: points[1].1,
             h: points[1].2,
-            index: offset + 1,
+            index: index(1),
             in_between: is_in_between(&points[0].1, &points[1].1, &points[2].1),
         },
+        polygon: ring.polygon,
+        interior: ring.interior,
     });
 
     edges
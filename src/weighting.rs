@@ -0,0 +1,60 @@
+// how the weight h of a vertex or point changes the distance to it
+// weight combines the Euclidean distance d with h
+// lower_bound may not exceed weight(d_t, h) for any d_t >= d and h >= hmin,
+// it is used to skip boxes which are at least d away and whose smallest
+// weight is hmin
+pub trait Weighting {
+    fn weight(&self, d: f64, h: f64) -> f64;
+    fn lower_bound(&self, d: f64, hmin: f64) -> f64;
+}
+
+// d + h, the weighting of build_search_tree_h and build_point_tree_h
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Additive;
+
+impl Weighting for Additive {
+    fn weight(&self, d: f64, h: f64) -> f64 {
+        d + h
+    }
+
+    fn lower_bound(&self, d: f64, hmin: f64) -> f64 {
+        d + hmin
+    }
+}
+
+// d * h, so that a site with a small h attracts points from farther away
+// (multiplicatively weighted Voronoi diagram)
+// negative weights make nearby boxes impossible to skip
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Multiplicative;
+
+impl Weighting for Multiplicative {
+    fn weight(&self, d: f64, h: f64) -> f64 {
+        d * h
+    }
+
+    fn lower_bound(&self, d: f64, hmin: f64) -> f64 {
+        if hmin >= 0.0 {
+            d * hmin
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+}
+
+// min(d, max_distance) + h: beyond max_distance all sites are equally far
+// and only h decides
+#[derive(Debug, Clone, Copy)]
+pub struct Capped {
+    pub max_distance: f64,
+}
+
+impl Weighting for Capped {
+    fn weight(&self, d: f64, h: f64) -> f64 {
+        d.min(self.max_distance) + h
+    }
+
+    fn lower_bound(&self, d: f64, hmin: f64) -> f64 {
+        d.min(self.max_distance) + hmin
+    }
+}
@@ -0,0 +1,200 @@
+// reading polygons and polylines from well-known binary (WKB)
+// supported are POLYGON, MULTIPOLYGON, and LINESTRING in either byte order,
+// with ISO (1000, 2000, 3000) or extended (EWKB flags) Z and M coordinates
+// which are dropped, and the SRID of extended WKB as written by PostGIS
+
+use crate::error::ReadError;
+use crate::polygon::{Geometry, Polygon};
+
+const LINESTRING: u32 = 2;
+const POLYGON: u32 = 3;
+const MULTIPOLYGON: u32 = 6;
+
+// flags of extended WKB in the geometry type
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+pub fn parse_wkb(bytes: &[u8]) -> Result<Geometry, ReadError> {
+    parse_ewkb(bytes).map(|(geometry, _)| geometry)
+}
+
+// also returns the SRID if the input is extended WKB with an SRID
+pub fn parse_ewkb(bytes: &[u8]) -> Result<(Geometry, Option<u32>), ReadError> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+        little_endian: true,
+    };
+
+    let header = reader.header()?;
+    let geometry = match header.geometry_type {
+        POLYGON => Geometry::Polygon(reader.polygon(header.dimensions)?),
+        MULTIPOLYGON => {
+            let num_polygons = reader.u32()?;
+            let mut polygons = Vec::new();
+            for _ in 0..num_polygons {
+                let position = reader.position;
+                let part = reader.header()?;
+                if part.geometry_type != POLYGON {
+                    return Err(ReadError::Malformed(format!(
+                        "the part at byte {} of a MULTIPOLYGON is not a POLYGON",
+                        position
+                    )));
+                }
+                polygons.push(reader.polygon(part.dimensions)?);
+            }
+            Geometry::MultiPolygon(polygons)
+        }
+        LINESTRING => Geometry::LineString(reader.points(header.dimensions)?),
+        geometry_type => {
+            return Err(ReadError::UnsupportedGeometry {
+                feature: 0,
+                geometry: match geometry_type {
+                    1 => "POINT",
+                    4 => "MULTIPOINT",
+                    5 => "MULTILINESTRING",
+                    _ => "GEOMETRYCOLLECTION",
+                },
+            });
+        }
+    };
+
+    if reader.position < bytes.len() {
+        return Err(ReadError::Malformed(format!(
+            "{} bytes follow the geometry",
+            bytes.len() - reader.position
+        )));
+    }
+
+    Ok((geometry, header.srid))
+}
+
+struct Header {
+    // 1 to 7 without Z, M, and SRID
+    geometry_type: u32,
+    // 2 to 4 coordinates per point
+    dimensions: usize,
+    srid: Option<u32>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    // byte order of the geometry which is read, every part has its own
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReadError> {
+        match self.bytes[self.position..].first_chunk::<N>() {
+            Some(array) => {
+                self.position += N;
+                Ok(*array)
+            }
+            None => Err(ReadError::Malformed(format!(
+                "the WKB ends after {} bytes but more are needed",
+                self.bytes.len()
+            ))),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, ReadError> {
+        let array = self.array()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(array)
+        } else {
+            u32::from_be_bytes(array)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, ReadError> {
+        let array = self.array()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(array)
+        } else {
+            f64::from_be_bytes(array)
+        })
+    }
+
+    fn header(&mut self) -> Result<Header, ReadError> {
+        let position = self.position;
+        self.little_endian = match self.array::<1>()? {
+            [0] => false,
+            [1] => true,
+            [byte] => {
+                return Err(ReadError::Malformed(format!(
+                    "the byte order at byte {} is {} but needs to be 0 or 1",
+                    position, byte
+                )));
+            }
+        };
+
+        let flags = self.u32()?;
+        let code = flags & 0x0fff_ffff;
+        let geometry_type = code % 1000;
+        if !(1..=7).contains(&geometry_type) || code / 1000 > 3 {
+            return Err(ReadError::Malformed(format!(
+                "unknown geometry type {} at byte {}",
+                code,
+                position + 1
+            )));
+        }
+
+        let mut dimensions = match code / 1000 {
+            0 => 2,
+            3 => 4,
+            _ => 3,
+        };
+        dimensions += usize::from(flags & EWKB_Z != 0) + usize::from(flags & EWKB_M != 0);
+        if dimensions > 4 {
+            return Err(ReadError::Malformed(format!(
+                "geometry type {:#x} at byte {} has Z or M twice",
+                flags,
+                position + 1
+            )));
+        }
+
+        let srid = if flags & EWKB_SRID != 0 {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+
+        Ok(Header {
+            geometry_type,
+            dimensions,
+            srid,
+        })
+    }
+
+    // the first ring is the exterior and the remaining rings are holes
+    fn polygon(&mut self, dimensions: usize) -> Result<Polygon, ReadError> {
+        let num_rings = self.u32()?;
+        let mut polygon = Polygon::default();
+        for i in 0..num_rings {
+            let ring = self.points(dimensions)?;
+            if i == 0 {
+                polygon.exterior = ring;
+            } else {
+                polygon.interiors.push(ring);
+            }
+        }
+        Ok(polygon)
+    }
+
+    // only x and y are kept
+    fn points(&mut self, dimensions: usize) -> Result<Vec<(f64, f64)>, ReadError> {
+        let num_points = self.u32()?;
+        let mut points = Vec::new();
+        for _ in 0..num_points {
+            let x = self.f64()?;
+            let y = self.f64()?;
+            for _ in 2..dimensions {
+                self.f64()?;
+            }
+            points.push((x, y));
+        }
+        Ok(points)
+    }
+}
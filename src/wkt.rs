@@ -0,0 +1,236 @@
+// reading polygons and polylines from well-known text (WKT)
+// supported are POLYGON, MULTIPOLYGON, and LINESTRING, also with Z and M
+// coordinates (these are dropped) and with the SRID=...; prefix of extended
+// WKT as written by PostGIS
+// keywords are case-insensitive
+
+use crate::error::ReadError;
+use crate::polygon::{Geometry, MultiPolygon, Polygon};
+
+// geometry types which are valid WKT but neither polygons nor polylines
+const UNSUPPORTED: [&str; 13] = [
+    "POINT",
+    "MULTIPOINT",
+    "MULTILINESTRING",
+    "GEOMETRYCOLLECTION",
+    "CIRCULARSTRING",
+    "COMPOUNDCURVE",
+    "CURVEPOLYGON",
+    "MULTICURVE",
+    "MULTISURFACE",
+    "POLYHEDRALSURFACE",
+    "TIN",
+    "TRIANGLE",
+    "GEOMETRY",
+];
+
+pub fn parse_wkt(text: &str) -> Result<Geometry, ReadError> {
+    let mut parser = Parser { text, position: 0 };
+
+    parser.srid()?;
+    let geometry = parser.geometry()?;
+
+    parser.skip_whitespace();
+    if parser.position < text.len() {
+        return Err(parser.error("the end of the text"));
+    }
+
+    Ok(geometry)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    // byte offset of the next character
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, expected: &str) -> ReadError {
+        ReadError::Malformed(format!(
+            "expected {} at position {} of the WKT",
+            expected, self.position
+        ))
+    }
+
+    // consumes c if it is the next character
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ReadError> {
+        if self.accept(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{}'", c)))
+        }
+    }
+
+    // a keyword or number, everything up to the next delimiter
+    fn token(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_')))
+            .unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    // consumes the next token only if it is the keyword
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let position = self.position;
+        if self.token().eq_ignore_ascii_case(keyword) {
+            true
+        } else {
+            self.position = position;
+            false
+        }
+    }
+
+    // the SRID is not needed for building a tree
+    fn srid(&mut self) -> Result<(), ReadError> {
+        self.skip_whitespace();
+        let prefix = self.rest().get(..5);
+        if prefix.is_some_and(|prefix| prefix.eq_ignore_ascii_case("SRID=")) {
+            self.position += 5;
+            if self.token().parse::<u32>().is_err() {
+                return Err(self.error("an SRID"));
+            }
+            self.expect(';')?;
+        }
+        Ok(())
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, ReadError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let tag = self.token().to_ascii_uppercase();
+
+        if let Some(name) = UNSUPPORTED.iter().find(|name| **name == tag) {
+            return Err(ReadError::UnsupportedGeometry {
+                feature: 0,
+                geometry: name,
+            });
+        }
+        if !matches!(tag.as_str(), "POLYGON" | "MULTIPOLYGON" | "LINESTRING") {
+            self.position = start;
+            return Err(self.error("POLYGON, MULTIPOLYGON, or LINESTRING"));
+        }
+
+        let dimensions = if self.accept_keyword("ZM") {
+            Some(4)
+        } else if self.accept_keyword("Z") || self.accept_keyword("M") {
+            Some(3)
+        } else {
+            None
+        };
+
+        let empty = self.accept_keyword("EMPTY");
+
+        Ok(match tag.as_str() {
+            "POLYGON" if empty => Geometry::Polygon(Polygon::default()),
+            "POLYGON" => Geometry::Polygon(self.polygon(dimensions)?),
+            "MULTIPOLYGON" if empty => Geometry::MultiPolygon(Vec::new()),
+            "MULTIPOLYGON" => Geometry::MultiPolygon(self.multipolygon(dimensions)?),
+            "LINESTRING" if empty => Geometry::LineString(Vec::new()),
+            _ => Geometry::LineString(self.points(dimensions)?),
+        })
+    }
+
+    fn multipolygon(&mut self, dimensions: Option<usize>) -> Result<MultiPolygon, ReadError> {
+        let mut polygons = Vec::new();
+
+        self.expect('(')?;
+        loop {
+            polygons.push(self.polygon(dimensions)?);
+            if !self.accept(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+
+        Ok(polygons)
+    }
+
+    // the first ring is the exterior and the remaining rings are holes
+    fn polygon(&mut self, dimensions: Option<usize>) -> Result<Polygon, ReadError> {
+        let mut polygon = Polygon::default();
+
+        self.expect('(')?;
+        polygon.exterior = self.points(dimensions)?;
+        while self.accept(',') {
+            polygon.interiors.push(self.points(dimensions)?);
+        }
+        self.expect(')')?;
+
+        Ok(polygon)
+    }
+
+    fn points(&mut self, dimensions: Option<usize>) -> Result<Vec<(f64, f64)>, ReadError> {
+        let mut points = Vec::new();
+
+        self.expect('(')?;
+        loop {
+            points.push(self.point(dimensions)?);
+            if !self.accept(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+
+        Ok(points)
+    }
+
+    // without Z or M a point may have 2 to 4 coordinates, only x and y are
+    // kept
+    fn point(&mut self, dimensions: Option<usize>) -> Result<(f64, f64), ReadError> {
+        let expected = match dimensions {
+            Some(4) => "4 coordinates",
+            Some(_) => "3 coordinates",
+            None => "2 to 4 coordinates",
+        };
+
+        let start = self.position;
+        let mut coordinates = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().is_empty() || self.rest().starts_with([',', ')']) {
+                break;
+            }
+            let position = self.position;
+            match self.token().parse::<f64>() {
+                Ok(coordinate) => coordinates.push(coordinate),
+                Err(_) => {
+                    self.position = position;
+                    return Err(self.error("a number"));
+                }
+            }
+        }
+
+        let valid = match dimensions {
+            Some(n) => coordinates.len() == n,
+            None => (2..=4).contains(&coordinates.len()),
+        };
+        if !valid {
+            self.position = start;
+            return Err(self.error(expected));
+        }
+
+        Ok((coordinates[0], coordinates[1]))
+    }
+}
@@ -1,5 +1,3 @@
-use polygons;
-
 use std::time::Instant;
 extern crate rand;
 use rand::Rng;
@@ -54,7 +52,7 @@ fn get_random_points(
 }
 
 fn floats_are_same(f1: f64, f2: f64) -> bool {
-    return (f1 - f2).abs() < std::f64::EPSILON;
+    (f1 - f2).abs() < f64::EPSILON
 }
 
 fn read_polygons(file_name: &str) -> Vec<Vec<(f64, f64, f64)>> {
@@ -96,7 +94,7 @@ fn read_polygons(file_name: &str) -> Vec<Vec<(f64, f64, f64)>> {
 }
 
 fn get_bounds(polygons: &[Vec<(f64, f64, f64)>]) -> (f64, f64, f64, f64) {
-    let large_number = std::f64::MAX;
+    let large_number = f64::MAX;
 
     let mut x_min = large_number;
     let mut x_max = -large_number;
@@ -119,7 +117,7 @@ fn distances_nearest_vertices_custom_naive(
     polygons: &[Vec<(f64, f64, f64)>],
     reference_points: &[(f64, f64)],
 ) -> (Vec<usize>, Vec<f64>) {
-    let large_number = std::f64::MAX;
+    let large_number = f64::MAX;
 
     let mut indices = Vec::new();
     let mut distances = Vec::new();
@@ -215,12 +213,1818 @@ fn custom_distance() {
     let tree = polygons::build_search_tree_h(polygons, 4, 4);
     let (indices, distances) = polygons::distances_nearest_vertices(&tree, &reference_points);
 
+    let indices_naive: Vec<Option<usize>> = indices_naive.into_iter().map(Some).collect();
     assert_eq!(indices, indices_naive);
     for (&x, &rx) in distances.iter().zip(distances_naive.iter()) {
         assert!(floats_are_same(x, rx));
     }
 }
 
+// the distance plus interpolated h along one edge is convex in the edge
+// parameter so a ternary search finds its minimum
+fn distance_edge_h_naive(p: (f64, f64), p1: (f64, f64, f64), p2: (f64, f64, f64)) -> f64 {
+    let f = |t: f64| {
+        let x = p1.0 + t * (p2.0 - p1.0);
+        let y = p1.1 + t * (p2.1 - p1.1);
+        (x - p.0).hypot(y - p.1) + p1.2 + t * (p2.2 - p1.2)
+    };
+    let (mut a, mut b) = (0.0, 1.0);
+    for _ in 0..100 {
+        let t1 = a + (b - a) / 3.0;
+        let t2 = b - (b - a) / 3.0;
+        if f(t1) < f(t2) {
+            b = t2;
+        } else {
+            a = t1;
+        }
+    }
+    f(0.5 * (a + b)).min(f(0.0)).min(f(1.0))
+}
+
+#[test]
+fn custom_edge_distance() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let (x_min, x_max, y_min, y_max) = get_bounds(&polygons);
+
+    let reference_points = get_random_points(20, x_min, x_max, y_min, y_max);
+
+    let tree = polygons::build_search_tree_h(polygons.clone(), 4, 4);
+    let distances = polygons::distances_nearest_edges_h(&tree, &reference_points);
+
+    for (&p, &d) in reference_points.iter().zip(distances.iter()) {
+        let mut d_naive = f64::MAX;
+        for polygon in &polygons {
+            let n = polygon.len();
+            for j in 0..n {
+                d_naive = d_naive.min(distance_edge_h_naive(p, polygon[j], polygon[(j + 1) % n]));
+            }
+        }
+        assert!((d - d_naive).abs() < 1.0e-6 * d_naive);
+    }
+
+    // without h this is the distance to the nearest edge
+    let tree = polygons::build_search_tree_h(zero_out_h(polygons), 4, 4);
+    assert_eq!(
+        polygons::distances_nearest_edges_h(&tree, &reference_points),
+        polygons::distances_nearest_edges(&tree, &reference_points)
+    );
+
+    // h grows from 0 to 4 along the bottom edge of the square: directly below
+    // (1, 0) the distance is 1 + 1, moving towards (0, 0) pays off since h
+    // changes faster than the distance
+    let square = vec![vec![
+        (0.0, 0.0, 0.0),
+        (4.0, 0.0, 4.0),
+        (4.0, 4.0, 4.0),
+        (0.0, 4.0, 4.0),
+    ]];
+    let tree = polygons::build_search_tree_h(square, 4, 4);
+    let distances = polygons::distances_nearest_edges_h(&tree, &[(1.0, -1.0)]);
+    assert!(floats_are_same(distances[0], 2.0_f64.sqrt()));
+}
+
+#[test]
+fn build_errors() {
+    let square = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+    assert_eq!(
+        polygons::try_build_search_tree(vec![square.clone(), vec![(0.0, 0.0), (1.0, 0.0)]], 4, 4)
+            .err(),
+        Some(polygons::BuildError::DegeneratePolygon {
+            polygon: 1,
+            ring: 0,
+            num_points: 2
+        })
+    );
+
+    assert_eq!(
+        polygons::try_build_search_tree(vec![square.clone()], 0, 4).err(),
+        Some(polygons::BuildError::InvalidFanOut {
+            parameter: "num_edges_children",
+            value: 0
+        })
+    );
+
+    assert_eq!(
+        polygons::try_build_search_tree(vec![square.clone()], 4, 1).err(),
+        Some(polygons::BuildError::InvalidFanOut {
+            parameter: "num_nodes_children",
+            value: 1
+        })
+    );
+
+    assert_eq!(
+        polygons::try_build_search_tree(
+            vec![
+                square.clone(),
+                vec![(0.0, 0.0), (f64::NAN, 0.0), (1.0, 1.0)]
+            ],
+            4,
+            4
+        )
+        .err(),
+        Some(polygons::BuildError::NonFiniteCoordinate {
+            polygon: 1,
+            ring: 0,
+            point: 1
+        })
+    );
+
+    assert!(polygons::try_build_search_tree(vec![square], 4, 4).is_ok());
+}
+
+#[test]
+fn closed_rings() {
+    let open = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+
+    // repeats the first point at the end and the third point right after it
+    let closed = vec![
+        (0.0, 0.0),
+        (2.0, 0.0),
+        (2.0, 2.0),
+        (2.0, 2.0),
+        (0.0, 2.0),
+        (0.0, 0.0),
+    ];
+
+    let points = vec![(1.0, 1.0), (0.0, 1.0), (3.0, 1.0), (0.1, 2.1), (-0.1, -0.1)];
+
+    // the second polygon only sees the ring once, its indices start after
+    // all points of the first polygon as given
+    let open_tree = polygons::build_search_tree(vec![open.clone(), open.clone()], 4, 4);
+    let closed_tree = polygons::build_search_tree(vec![closed.clone(), open.clone()], 4, 4);
+
+    assert_eq!(
+        polygons::points_are_inside(&closed_tree, &points),
+        polygons::points_are_inside(&open_tree, &points)
+    );
+    assert_eq!(
+        polygons::classify_points(&closed_tree, &points, 0.0),
+        polygons::classify_points(&open_tree, &points, 0.0)
+    );
+    assert_eq!(
+        polygons::distances_nearest_edges(&closed_tree, &points),
+        polygons::distances_nearest_edges(&open_tree, &points)
+    );
+
+    // vertex indices refer to the arrays as they were given
+    let (indices, _) = polygons::distances_nearest_vertices(&closed_tree, &points[3..]);
+    assert_eq!(indices, vec![Some(4), Some(0)]);
+    let (indices, _) = polygons::distances_nearest_vertices(&open_tree, &points[3..]);
+    assert_eq!(indices, vec![Some(3), Some(0)]);
+    assert_eq!(closed_tree.num_vertices(), 10);
+
+    // no zero-length edges are left
+    let (_, indices) = polygons::edges_within(&closed_tree, &[(1.0, 1.0)], 10.0);
+    assert_eq!(indices, vec![0, 1, 2, 4, 6, 7, 8, 9]);
+
+    assert_eq!(
+        polygons::try_build_search_tree(vec![vec![(0.0, 0.0), (1.0, 0.0), (0.0, 0.0)]], 4, 4).err(),
+        Some(polygons::BuildError::DegeneratePolygon {
+            polygon: 0,
+            ring: 0,
+            num_points: 2
+        })
+    );
+}
+
+#[test]
+fn polylines() {
+    use polygons::{BuildError, QueryError};
+
+    let river = vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)];
+    let tree = polygons::build_search_tree_polylines(vec![river.clone()], 4, 4);
+    assert!(tree.has_polylines());
+
+    // as a polygon the point would lie on the closing edge
+    let points = vec![(1.0, 1.0), (-1.0, 0.0)];
+    let distances = polygons::distances_nearest_edges(&tree, &points);
+    assert_eq!(distances, vec![1.0, 1.0]);
+    let (indices, _) = polygons::distances_nearest_vertices(&tree, &[(1.9, 0.2), (-1.0, 0.0)]);
+    assert_eq!(indices, vec![Some(1), Some(0)]);
+    let (_, indices) = polygons::edges_within(&tree, &[(1.0, 1.0)], 10.0);
+    assert_eq!(indices, vec![0, 1]);
+
+    assert_eq!(
+        polygons::try_points_are_inside(&tree, &points).unwrap_err(),
+        QueryError::OpenPolylines
+    );
+    assert_eq!(
+        polygons::try_classify_points(&tree, &points, 0.0).unwrap_err(),
+        QueryError::OpenPolylines
+    );
+    assert_eq!(
+        polygons::try_signed_distances(&tree, &points).unwrap_err(),
+        QueryError::OpenPolylines
+    );
+    assert_eq!(
+        polygons::try_containing_polygons(&tree, &points).unwrap_err(),
+        QueryError::OpenPolylines
+    );
+
+    // a polyline which ends where it starts keeps its last edge
+    let loop_line = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)];
+    let tree = polygons::build_search_tree_polylines(vec![loop_line], 4, 4);
+    let (_, indices) = polygons::edges_within(&tree, &[(1.0, 1.0)], 10.0);
+    assert_eq!(indices, vec![0, 1, 2]);
+
+    // polylines get the ids which follow those of the polygons
+    let square = vec![(3.0, 0.0), (4.0, 0.0), (4.0, 1.0), (3.0, 1.0)];
+    let tree = polygons::TreeBuilder::new()
+        .build_with_polylines(vec![square.clone()], vec![river.clone()])
+        .unwrap();
+    let nearest = polygons::nearest_edges(&tree, &[(1.0, 0.5), (3.5, 1.5)]);
+    assert_eq!(nearest[0].unwrap().polygon, 1);
+    assert_eq!(nearest[1].unwrap().polygon, 0);
+    assert_eq!(tree.num_vertices(), 7);
+
+    let loaded = polygons::Tree::from_bytes(&tree.to_bytes()).unwrap();
+    assert!(loaded.has_polylines());
+
+    let mut tree = polygons::build_search_tree(vec![square], 4, 4);
+    assert!(!tree.has_polylines());
+    assert_eq!(tree.insert_polyline(river).unwrap(), 1);
+    assert!(tree.has_polylines());
+    assert!(polygons::try_points_are_inside(&tree, &points).is_err());
+
+    assert_eq!(
+        polygons::try_build_search_tree_polylines(vec![vec![(0.0, 0.0), (0.0, 0.0)]], 4, 4).err(),
+        Some(BuildError::DegeneratePolyline {
+            polyline: 0,
+            num_points: 1
+        })
+    );
+}
+
+#[test]
+fn empty_tree() {
+    let tree = polygons::build_search_tree(Vec::new(), 4, 4);
+    assert!(tree.is_empty());
+
+    let points = vec![(0.5, 0.5), (2.0, -1.0)];
+
+    assert_eq!(
+        polygons::points_are_inside(&tree, &points),
+        vec![false, false]
+    );
+
+    assert_eq!(
+        polygons::distances_nearest_edges(&tree, &points),
+        vec![f64::INFINITY, f64::INFINITY]
+    );
+
+    let (indices, distances) = polygons::distances_nearest_vertices(&tree, &points);
+    assert_eq!(indices, vec![None, None]);
+    assert_eq!(distances, vec![f64::INFINITY, f64::INFINITY]);
+}
+
+#[test]
+fn containing_polygons() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let polygons = zero_out_h(polygons);
+
+    let tree = polygons::build_search_tree_h(polygons, 4, 4);
+
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+
+    let contains = polygons::points_are_inside(&tree, &reference_points);
+    let containing = polygons::containing_polygons(&tree, &reference_points);
+    for (&x, polygon) in contains.iter().zip(containing.iter()) {
+        assert_eq!(x, polygon.is_some());
+    }
+
+    let overlapping = vec![
+        vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+        vec![(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)],
+    ];
+    let tree = polygons::build_search_tree(overlapping, 4, 4);
+    let points = vec![(0.5, 0.5), (1.5, 1.5), (2.5, 2.5), (5.8, 5.2), (4.0, 4.0)];
+
+    assert_eq!(
+        polygons::containing_polygons(&tree, &points),
+        vec![Some(0), Some(0), Some(1), Some(2), None]
+    );
+    assert_eq!(
+        polygons::all_containing_polygons(&tree, &points),
+        vec![vec![0], vec![0, 1], vec![1], vec![2], vec![]]
+    );
+}
+
+#[test]
+fn payloads() {
+    // payloads need not be cloneable
+    #[derive(Debug, PartialEq)]
+    struct Island(usize);
+
+    let polygons = zero_out_h(read_polygons("tests/case-1/islands.txt"));
+    let num_polygons = polygons.len();
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+
+    let tree = polygons::build_search_tree_h(polygons.clone(), 4, 4)
+        .with_payloads((0..num_polygons).map(Island));
+
+    let containing = polygons::containing_polygons(&tree, &reference_points);
+    let payloads = polygons::containing_payloads(&tree, &reference_points);
+    for (polygon, payload) in containing.iter().zip(payloads) {
+        assert_eq!(polygon.map(Island).as_ref(), payload);
+    }
+
+    // vertex indices lead back to their polygon without a table of offsets
+    let mut owners = Vec::new();
+    for (i, polygon) in polygons.iter().enumerate() {
+        owners.extend(std::iter::repeat_n(i, polygon.len()));
+    }
+    let (indices, _) = polygons::k_nearest_vertices(&tree, &reference_points[..100], 3);
+    for &index in indices.iter().flatten() {
+        assert_eq!(tree.vertex_polygon(index), Some(owners[index]));
+        assert_eq!(tree.vertex_payload(index), Some(&Island(owners[index])));
+    }
+    assert_eq!(tree.vertex_polygon(owners.len()), None);
+
+    // the payloads of the squares are their names, the triangle has none
+    let shapes = vec![
+        vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        vec![(2.0, 0.0), (3.0, 0.0), (3.0, 1.0), (2.0, 1.0)],
+        vec![(5.0, 5.0), (6.0, 5.0), (6.0, 6.0)],
+    ];
+    let mut tree = polygons::build_search_tree(shapes, 4, 4).with_payloads(["left", "right"]);
+    let points = [(0.5, 0.5), (2.5, 0.5), (5.8, 5.2), (9.0, 9.0)];
+    assert_eq!(
+        polygons::containing_payloads(&tree, &points),
+        vec![Some(&"left"), Some(&"right"), None, None]
+    );
+
+    assert_eq!(tree.set_payload(2, "triangle"), Ok(None));
+    assert_eq!(tree.set_payload(0, "first"), Ok(Some("left")));
+    assert_eq!(
+        tree.set_payload(3, "missing"),
+        Err(polygons::QueryError::UnknownPolygon { polygon: 3 })
+    );
+
+    let square = tree
+        .insert_polygon(vec![(0.0, 3.0), (1.0, 3.0), (1.0, 4.0), (0.0, 4.0)])
+        .unwrap();
+    tree.set_payload(square, "inserted").unwrap();
+    assert_eq!(tree.vertex_polygon(13), Some(square));
+    tree.remove_polygon(1).unwrap();
+    assert_eq!(tree.payload(1), None);
+    // the vertices of the removed square no longer belong to any polygon
+    assert_eq!(tree.vertex_polygon(3), Some(0));
+    assert_eq!(tree.vertex_polygon(4), None);
+    assert_eq!(tree.vertex_polygon(7), None);
+    assert_eq!(tree.vertex_payload(5), None);
+    assert_eq!(tree.vertex_polygon(8), Some(2));
+    assert_eq!(
+        polygons::containing_payloads(&tree, &[(0.5, 3.5), (2.5, 0.5), (5.8, 5.2)]),
+        vec![Some(&"inserted"), None, Some(&"triangle")]
+    );
+
+    // saved trees have no payloads but still know their polygons
+    let loaded = polygons::Tree::from_bytes(&tree.to_bytes()).unwrap();
+    assert_eq!(loaded.payloads().count(), 0);
+    assert_eq!(loaded.vertex_polygon(13), Some(square));
+    assert_eq!(loaded.vertex_polygon(8), Some(2));
+    assert_eq!(loaded.vertex_polygon(3), Some(0));
+    assert_eq!(loaded.vertex_polygon(5), None);
+
+    // a null shape in the middle gets an id but is not in the tree, with or
+    // without polygon ids
+    let triangle = |x: f64| polygons::Polygon::from(vec![(x, 0.0), (x + 1.0, 0.0), (x, 1.0)]);
+    let multipolygons = vec![vec![triangle(0.0)], Vec::new(), vec![triangle(2.0)]];
+    for store_polygon_ids in [true, false] {
+        let tree = polygons::TreeBuilder::new()
+            .store_polygon_ids(store_polygon_ids)
+            .build_multipolygons(multipolygons.clone())
+            .unwrap();
+        let loaded = polygons::Tree::from_bytes(&tree.to_bytes()).unwrap();
+        for tree in [tree, loaded] {
+            assert_eq!(tree.num_polygons(), 3);
+            let mut tree = tree.with_payloads(["first", "null", "third"]);
+            assert_eq!(tree.payload(0), Some(&"first"));
+            assert_eq!(tree.payload(1), None);
+            assert_eq!(tree.payload(2), Some(&"third"));
+            assert_eq!(
+                tree.set_payload(1, "null"),
+                Err(polygons::QueryError::UnknownPolygon { polygon: 1 })
+            );
+        }
+    }
+}
+
+#[cfg(feature = "geojson")]
+#[test]
+fn geojson() {
+    use geojson::{GeoJson, JsonValue};
+
+    // a square with a square hole, two triangles, and a line
+    let input = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "properties": {"name": "frame"},
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [
+                        [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+                        [[1.0, 1.0], [3.0, 1.0], [3.0, 3.0], [1.0, 3.0], [1.0, 1.0]]
+                    ]
+                }
+            },
+            {
+                "type": "Feature",
+                "properties": {"name": "triangles"},
+                "geometry": {
+                    "type": "MultiPolygon",
+                    "coordinates": [
+                        [[[10.0, 0.0], [12.0, 0.0], [11.0, 2.0], [10.0, 0.0]]],
+                        [[[20.0, 0.0], [22.0, 0.0], [21.0, 2.0], [20.0, 0.0]]]
+                    ]
+                }
+            },
+            {
+                "type": "Feature",
+                "properties": null,
+                "geometry": {
+                    "type": "LineString",
+                    "coordinates": [[0.0, 10.0], [5.0, 10.0]]
+                }
+            }
+        ]
+    }"#;
+    let geojson: GeoJson = input.parse().unwrap();
+    let tree = polygons::TreeBuilder::new()
+        .build_geojson(&geojson)
+        .unwrap();
+
+    let name = |i: usize| tree.payload(i).and_then(|p| p.get("name")).cloned();
+    assert_eq!(name(0), Some(JsonValue::from("frame")));
+    assert_eq!(name(1), Some(JsonValue::from("triangles")));
+    assert_eq!(tree.payload(2).map(|p| p.is_empty()), Some(true));
+
+    let points = [
+        (0.5, 0.5),
+        (2.0, 2.0),
+        (11.0, 0.5),
+        (21.0, 0.5),
+        (2.0, 11.0),
+    ];
+    let collection = polygons::points_to_geojson(&tree, &points).unwrap();
+    assert_eq!(collection.features.len(), points.len());
+    let property = |i: usize, key: &str| collection.features[i].property(key).cloned();
+    // trees with polylines cannot tell inside from outside
+    assert_eq!(property(0, "inside"), None);
+    assert_eq!(property(1, "distance"), Some(JsonValue::from(1.0)));
+    assert_eq!(property(1, "nearest_polygon"), Some(JsonValue::from(0)));
+    assert_eq!(property(4, "distance"), Some(JsonValue::from(1.0)));
+    assert_eq!(property(4, "nearest_polygon"), Some(JsonValue::from(2)));
+    // vertices are counted over all positions, closing ones included
+    assert_eq!(property(4, "nearest_index"), Some(JsonValue::from(18)));
+
+    // without the line the points can be classified
+    let polygons_only = GeoJson::FeatureCollection(geojson::FeatureCollection {
+        features: match &geojson {
+            GeoJson::FeatureCollection(collection) => collection.features[..2].to_vec(),
+            _ => unreachable!(),
+        },
+        bbox: None,
+        foreign_members: None,
+    });
+    let tree = polygons::TreeBuilder::new()
+        .build_geojson(&polygons_only)
+        .unwrap();
+    assert_eq!(
+        polygons::containing_polygons(&tree, &points),
+        vec![Some(0), None, Some(1), Some(1), None]
+    );
+    let collection = polygons::points_to_geojson(&tree, &points).unwrap();
+    let inside: Vec<_> = collection
+        .features
+        .iter()
+        .map(|feature| feature.property("inside").cloned())
+        .collect();
+    assert_eq!(
+        inside,
+        [true, false, true, true, false].map(|b| Some(JsonValue::from(b)))
+    );
+
+    // a feature without geometry keeps its id but is not in the tree
+    let input = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "properties": {"name": "first"},
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]]
+                }
+            },
+            {"type": "Feature", "properties": {"name": "nothing"}, "geometry": null},
+            {
+                "type": "Feature",
+                "properties": {"name": "third"},
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[[5.0, 0.0], [6.0, 0.0], [5.0, 1.0], [5.0, 0.0]]]
+                }
+            }
+        ]
+    }"#;
+    let tree = polygons::TreeBuilder::new()
+        .build_geojson(&input.parse().unwrap())
+        .unwrap();
+    assert_eq!(tree.num_polygons(), 3);
+    assert_eq!(tree.payload(1), None);
+    let name = |i: usize| tree.payload(i).and_then(|p| p.get("name")).cloned();
+    assert_eq!(name(2), Some(JsonValue::from("third")));
+    assert_eq!(
+        polygons::containing_polygons(&tree, &[(0.2, 0.2), (5.2, 0.2)]),
+        vec![Some(0), Some(2)]
+    );
+
+    // points are neither polygons nor polylines
+    let point: GeoJson = r#"{"type": "Point", "coordinates": [1.0, 2.0]}"#.parse().unwrap();
+    assert_eq!(
+        polygons::TreeBuilder::new()
+            .build_geojson(&point)
+            .unwrap_err(),
+        polygons::ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "Point"
+        }
+    );
+}
+
+#[cfg(feature = "wkt")]
+#[test]
+fn wkt() {
+    use polygons::{Geometry, Polygon, ReadError};
+
+    let frame = Polygon {
+        exterior: vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)],
+        interiors: vec![vec![
+            (1.0, 1.0),
+            (3.0, 1.0),
+            (3.0, 3.0),
+            (1.0, 3.0),
+            (1.0, 1.0),
+        ]],
+    };
+    assert_eq!(
+        polygons::parse_wkt("POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 3 1, 3 3, 1 3, 1 1))"),
+        Ok(Geometry::Polygon(frame.clone()))
+    );
+    // case, whitespace, SRID, and Z coordinates do not matter
+    assert_eq!(
+        polygons::parse_wkt(
+            " srid=4326;polygon z((0 0 9,4 0 9,4 4 9,0 4 9,0 0 9),(1 1 9,3 1 9,3 3 9,1 3 9,1 1 9)) "
+        ),
+        Ok(Geometry::Polygon(frame))
+    );
+
+    let geometries: Vec<Geometry> = [
+        "POLYGON ((0 0, 4 0, 4 4, 0 4, 0 0), (1 1, 3 1, 3 3, 1 3, 1 1))",
+        "MULTIPOLYGON (((10 0, 12 0, 11 2, 10 0)), ((20 0, 22 0, 21 2, 20 0)))",
+        "LINESTRING (0 10, 5e0 10.0)",
+        "MULTIPOLYGON EMPTY",
+    ]
+    .iter()
+    .map(|text| polygons::parse_wkt(text).unwrap())
+    .collect();
+    let tree = polygons::TreeBuilder::new()
+        .build_geometries(geometries)
+        .unwrap();
+    let points = [(0.5, 0.5), (2.0, 2.0), (21.0, 0.5), (2.0, 11.0)];
+    let nearest: Vec<_> = polygons::nearest_edges(&tree, &points)
+        .iter()
+        .map(|edge| edge.unwrap().polygon)
+        .collect();
+    assert_eq!(nearest, vec![0, 0, 1, 2]);
+
+    for text in [
+        "",
+        "POLYGON",
+        "POLYGON ((0 0, 1 0, 1 1)",
+        "POLYGON ((0 0, 1 0, 1 x))",
+        "POLYGON ((0 0, 1 0, 1))",
+        "POLYGON Z ((0 0, 1 0, 1 1))",
+        "POLYGON ((0 0, 1 0, 1 1)) extra",
+        "SRID=x;POLYGON ((0 0, 1 0, 1 1))",
+        "TRIANGLES ((0 0, 1 0, 1 1))",
+        "POLYGON ((0 0, 1 0, 1 1)) é",
+    ] {
+        assert!(
+            matches!(polygons::parse_wkt(text), Err(ReadError::Malformed(_))),
+            "{}",
+            text
+        );
+    }
+    assert_eq!(
+        polygons::parse_wkt("POINT (1 2)"),
+        Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "POINT"
+        })
+    );
+}
+
+#[cfg(feature = "wkb")]
+#[test]
+fn wkb() {
+    use polygons::{Geometry, Polygon, ReadError};
+
+    // header, number of rings, and for each ring number of points and points
+    fn polygon_wkb(little_endian: bool, geometry_type: u32, rings: &[&[&[f64]]]) -> Vec<u8> {
+        let u32_bytes = |value: u32| {
+            if little_endian {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        };
+        let mut bytes = vec![u8::from(little_endian)];
+        bytes.extend(u32_bytes(geometry_type));
+        if geometry_type & 0x2000_0000 != 0 {
+            bytes.extend(u32_bytes(4326));
+        }
+        bytes.extend(u32_bytes(rings.len() as u32));
+        for ring in rings {
+            bytes.extend(u32_bytes(ring.len() as u32));
+            for coordinate in ring.iter().flat_map(|point| point.iter()) {
+                if little_endian {
+                    bytes.extend(coordinate.to_le_bytes());
+                } else {
+                    bytes.extend(coordinate.to_be_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    let triangle: &[&[f64]] = &[&[0.0, 0.0], &[1.0, 0.0], &[1.0, 1.0], &[0.0, 0.0]];
+    let expected = Geometry::Polygon(Polygon::from(vec![
+        (0.0, 0.0),
+        (1.0, 0.0),
+        (1.0, 1.0),
+        (0.0, 0.0),
+    ]));
+    for little_endian in [true, false] {
+        let bytes = polygon_wkb(little_endian, 3, &[triangle]);
+        assert_eq!(polygons::parse_wkb(&bytes), Ok(expected.clone()));
+        assert_eq!(polygons::parse_ewkb(&bytes), Ok((expected.clone(), None)));
+    }
+
+    // ISO and extended Z coordinates are dropped, the SRID is kept
+    let triangle_z: &[&[f64]] = &[
+        &[0.0, 0.0, 5.0],
+        &[1.0, 0.0, 5.0],
+        &[1.0, 1.0, 5.0],
+        &[0.0, 0.0, 5.0],
+    ];
+    let bytes = polygon_wkb(true, 1003, &[triangle_z]);
+    assert_eq!(polygons::parse_wkb(&bytes), Ok(expected.clone()));
+    let bytes = polygon_wkb(false, 0x8000_0003 | 0x2000_0000, &[triangle_z]);
+    assert_eq!(
+        polygons::parse_ewkb(&bytes),
+        Ok((expected.clone(), Some(4326)))
+    );
+
+    // a multipolygon of two parts in different byte orders
+    let mut bytes = vec![1, 6, 0, 0, 0, 2, 0, 0, 0];
+    bytes.extend(polygon_wkb(true, 3, &[triangle]));
+    bytes.extend(polygon_wkb(false, 3, &[triangle]));
+    assert_eq!(
+        polygons::parse_wkb(&bytes),
+        Ok(Geometry::MultiPolygon(vec![
+            Polygon::from(vec![
+                (0.0, 0.0),
+                (1.0, 0.0),
+                (1.0, 1.0),
+                (0.0, 0.0)
+            ]);
+            2
+        ]))
+    );
+
+    // a linestring has the layout of a single ring
+    let mut bytes = polygon_wkb(true, 2, &[&[&[0.0, 0.0], &[2.0, 0.0]]]);
+    bytes.drain(5..9);
+    assert_eq!(
+        polygons::parse_wkb(&bytes),
+        Ok(Geometry::LineString(vec![(0.0, 0.0), (2.0, 0.0)]))
+    );
+
+    let valid = polygon_wkb(true, 3, &[triangle]);
+    let mut extra = valid.clone();
+    extra.push(0);
+    let mut byte_order = valid.clone();
+    byte_order[0] = 2;
+    for bytes in [
+        &[][..],
+        &valid[..valid.len() - 1],
+        &extra,
+        &byte_order,
+        &polygon_wkb(true, 9, &[triangle]),
+        &polygon_wkb(true, 0x8000_3003, &[triangle_z]),
+    ] {
+        assert!(matches!(
+            polygons::parse_wkb(bytes),
+            Err(ReadError::Malformed(_))
+        ));
+    }
+    assert_eq!(
+        polygons::parse_wkb(&[
+            1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        ]),
+        Err(ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "POINT"
+        })
+    );
+}
+
+// writes the .shp and .dbf contents of a shapefile, shapes are the parts of
+// each shape and null shapes have none, the dbf has one text and one number
+// field
+#[cfg(feature = "shapefile")]
+fn write_shapefile(
+    shape_type: u32,
+    shapes: &[Vec<Vec<(f64, f64)>>],
+    names: &[&str],
+) -> (Vec<u8>, Vec<u8>) {
+    let mut records = Vec::new();
+    for (i, shape) in shapes.iter().enumerate() {
+        let mut content = Vec::new();
+        match shape {
+            parts if parts.is_empty() => content.extend(0u32.to_le_bytes()),
+            parts if shape_type == 1 => {
+                content.extend(1u32.to_le_bytes());
+                content.extend(parts[0][0].0.to_le_bytes());
+                content.extend(parts[0][0].1.to_le_bytes());
+            }
+            parts => {
+                content.extend(shape_type.to_le_bytes());
+                content.extend([0; 32]);
+                content.extend((parts.len() as u32).to_le_bytes());
+                let num_points: usize = parts.iter().map(|part| part.len()).sum();
+                content.extend((num_points as u32).to_le_bytes());
+                let mut start = 0;
+                for part in parts {
+                    content.extend((start as u32).to_le_bytes());
+                    start += part.len();
+                }
+                for &(x, y) in parts.iter().flatten() {
+                    content.extend(x.to_le_bytes());
+                    content.extend(y.to_le_bytes());
+                }
+            }
+        }
+        records.extend((i as u32 + 1).to_be_bytes());
+        records.extend((content.len() as u32 / 2).to_be_bytes());
+        records.extend(content);
+    }
+
+    let mut shp = Vec::new();
+    shp.extend(9994u32.to_be_bytes());
+    shp.extend([0; 20]);
+    shp.extend(((100 + records.len()) as u32 / 2).to_be_bytes());
+    shp.extend(1000u32.to_le_bytes());
+    shp.extend(shape_type.to_le_bytes());
+    shp.extend([0; 64]);
+    shp.extend(records);
+
+    let mut dbf = vec![3, 124, 1, 1];
+    dbf.extend((names.len() as u32).to_le_bytes());
+    dbf.extend(97u16.to_le_bytes());
+    dbf.extend(16u16.to_le_bytes());
+    dbf.extend([0; 20]);
+    for (name, kind, length) in [("NAME", b'C', 10), ("ID", b'N', 5)] {
+        let mut descriptor = [0; 32];
+        descriptor[..name.len()].copy_from_slice(name.as_bytes());
+        descriptor[11] = kind;
+        descriptor[16] = length;
+        dbf.extend(descriptor);
+    }
+    dbf.push(0x0d);
+    for (i, name) in names.iter().enumerate() {
+        dbf.extend(format!(" {:<10}{:>5}", name, i * 10).as_bytes());
+    }
+    dbf.push(0x1a);
+
+    (shp, dbf)
+}
+
+#[cfg(feature = "shapefile")]
+#[test]
+fn shapefile() {
+    use polygons::{Attribute, ReadError};
+
+    // outer rings are clockwise and holes counterclockwise
+    let frame = vec![
+        vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (4.0, 0.0), (0.0, 0.0)],
+        vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0), (1.0, 1.0)],
+    ];
+    let triangles = vec![
+        vec![(10.0, 0.0), (11.0, 2.0), (12.0, 0.0), (10.0, 0.0)],
+        vec![(20.0, 0.0), (21.0, 2.0), (22.0, 0.0), (20.0, 0.0)],
+    ];
+    let (shp, dbf) = write_shapefile(
+        5,
+        &[frame, Vec::new(), triangles],
+        &["frame", "nothing", "triangles"],
+    );
+    let tree = polygons::TreeBuilder::new()
+        .build_shapefile(&shp, &dbf)
+        .unwrap();
+
+    assert_eq!(
+        tree.payload(0).unwrap()["NAME"],
+        Attribute::Text("frame".to_string())
+    );
+    assert_eq!(tree.payload(2).unwrap()["ID"], Attribute::Number(20.0));
+    // null shapes are not in the tree
+    assert_eq!(tree.payload(1), None);
+
+    let points = [
+        (0.5, 0.5),
+        (2.0, 2.0),
+        (11.0, 0.5),
+        (21.0, 0.5),
+        (30.0, 0.0),
+    ];
+    assert_eq!(
+        polygons::containing_polygons(&tree, &points),
+        vec![Some(0), None, Some(2), Some(2), None]
+    );
+    // the hole is the only interior ring
+    assert_eq!(
+        polygons::distances_nearest_edges_with_boundary(
+            &tree,
+            &[(2.0, 2.0)],
+            polygons::Boundary::Interior
+        ),
+        vec![1.0]
+    );
+    assert_eq!(
+        polygons::distances_nearest_edges_with_boundary(
+            &tree,
+            &[(0.5, 2.0)],
+            polygons::Boundary::Exterior
+        ),
+        vec![0.5]
+    );
+
+    // polylines keep their parts open
+    let (shp, dbf) = write_shapefile(
+        3,
+        &[vec![
+            vec![(0.0, 0.0), (4.0, 0.0)],
+            vec![(0.0, 2.0), (4.0, 2.0)],
+        ]],
+        &["river"],
+    );
+    let tree = polygons::TreeBuilder::new()
+        .build_shapefile(&shp, &dbf)
+        .unwrap();
+    assert!(tree.has_polylines());
+    assert_eq!(
+        polygons::distances_nearest_edges(&tree, &[(2.0, 1.5), (6.0, 0.0)]),
+        vec![0.5, 2.0]
+    );
+
+    // the attributes follow the points, null shapes are dropped
+    let (shp, dbf) = write_shapefile(
+        1,
+        &[vec![vec![(0.0, 0.0)]], Vec::new(), vec![vec![(5.0, 0.0)]]],
+        &["first", "nothing", "third"],
+    );
+    let (tree, attributes) = polygons::TreeBuilder::new()
+        .build_shapefile_points(&shp, &dbf)
+        .unwrap();
+    assert_eq!(tree.len(), 2);
+    let (indices, _) = polygons::distances_nearest_points(&tree, &[(4.0, 0.0)]);
+    assert_eq!(
+        attributes[indices[0].unwrap()]["NAME"],
+        Attribute::Text("third".to_string())
+    );
+
+    // point shapefiles give no polygon tree and the other way around
+    assert_eq!(
+        polygons::TreeBuilder::new()
+            .build_shapefile(&shp, &dbf)
+            .unwrap_err(),
+        ReadError::UnsupportedGeometry {
+            feature: 0,
+            geometry: "point"
+        }
+    );
+
+    // each shape needs a record
+    let (_, dbf_too_short) = write_shapefile(1, &[Vec::new(), Vec::new()], &["first", "second"]);
+    assert!(matches!(
+        polygons::TreeBuilder::new().build_shapefile_points(&shp, &dbf_too_short),
+        Err(ReadError::Malformed(_))
+    ));
+    assert!(matches!(
+        polygons::TreeBuilder::new().build_shapefile_points(&shp[..shp.len() - 1], &dbf),
+        Err(ReadError::Malformed(_))
+    ));
+    let mut invalid = shp.clone();
+    invalid[3] = 0;
+    assert!(matches!(
+        polygons::TreeBuilder::new().build_shapefile_points(&invalid, &dbf),
+        Err(ReadError::Malformed(_))
+    ));
+
+    // the header length may not point into the field descriptors
+    let mut invalid = dbf.clone();
+    invalid[8..10].copy_from_slice(&64u16.to_le_bytes());
+    assert!(matches!(
+        polygons::TreeBuilder::new().build_shapefile_points(&shp, &invalid),
+        Err(ReadError::Malformed(reason)) if reason.contains("field descriptors")
+    ));
+}
+
+#[test]
+fn nearest_edges() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let polygons = zero_out_h(polygons);
+
+    let tree = polygons::build_search_tree_h(polygons, 4, 4);
+
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+
+    let nearest = polygons::nearest_edges(&tree, &reference_points);
+    let reference_distances = read_vector("tests/case-1/reference/distances_nearest_edges.txt");
+    for (edge, &rx) in nearest.iter().zip(reference_distances.iter()) {
+        assert!(floats_are_same(edge.unwrap().distance, rx));
+    }
+
+    let squares = vec![
+        vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        vec![(0.0, 2.0), (1.0, 2.0), (1.0, 3.0), (0.0, 3.0)],
+    ];
+    let tree = polygons::build_search_tree(squares, 4, 4);
+
+    let nearest = polygons::nearest_edges(&tree, &[(0.25, -0.5), (0.5, 3.5)]);
+    assert_eq!(
+        nearest,
+        vec![
+            Some(polygons::NearestEdge {
+                polygon: 0,
+                index: 0,
+                t: 0.25,
+                x: 0.25,
+                y: 0.0,
+                distance: 0.5,
+            }),
+            Some(polygons::NearestEdge {
+                polygon: 1,
+                index: 6,
+                t: 0.5,
+                x: 0.5,
+                y: 3.0,
+                distance: 0.5,
+            }),
+        ]
+    );
+}
+
+#[test]
+fn signed_distances() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let polygons = zero_out_h(polygons);
+
+    let tree = polygons::build_search_tree_h(polygons, 4, 4);
+
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+
+    let distances = polygons::signed_distances(&tree, &reference_points);
+    let reference_distances: Vec<f64> =
+        read_vector("tests/case-1/reference/distances_nearest_edges.txt");
+    let reference_bools: Vec<bool> = read_vector("tests/case-1/reference/points_are_inside.txt");
+    for ((&x, &rx), &inside) in distances
+        .iter()
+        .zip(reference_distances.iter())
+        .zip(reference_bools.iter())
+    {
+        if inside {
+            assert!(floats_are_same(x, -rx));
+        } else {
+            assert!(floats_are_same(x, rx));
+        }
+    }
+}
+
+#[test]
+fn k_nearest() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let (x_min, x_max, y_min, y_max) = get_bounds(&polygons);
+
+    let reference_points = get_random_points(200, x_min, x_max, y_min, y_max);
+
+    let k = 5;
+
+    let tree = polygons::build_search_tree_h(polygons.clone(), 4, 4);
+    let (indices, distances) = polygons::k_nearest_vertices(&tree, &reference_points, k);
+
+    for (i, &(rx, ry)) in reference_points.iter().enumerate() {
+        let mut naive: Vec<(f64, usize)> = polygons
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(j, (x, y, h))| (((x - rx).powi(2) + (y - ry).powi(2)).sqrt() + h, j))
+            .collect();
+        naive.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        assert_eq!(indices[i].len(), k);
+        for l in 0..k {
+            assert_eq!(indices[i][l], naive[l].1);
+            assert!(floats_are_same(distances[i][l], naive[l].0));
+        }
+    }
+
+    let tree = polygons::build_search_tree_h(zero_out_h(polygons), 4, 4);
+    let nearest = polygons::distances_nearest_edges(&tree, &reference_points);
+    let (_, distances) = polygons::k_nearest_edges(&tree, &reference_points, k);
+    for (d, &d_nearest) in distances.iter().zip(nearest.iter()) {
+        assert_eq!(d.len(), k);
+        assert!(floats_are_same(d[0], d_nearest));
+        assert!(d.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    let square = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]];
+    let tree = polygons::build_search_tree(square, 4, 4);
+    let (indices, distances) = polygons::k_nearest_edges(&tree, &[(0.5, -1.0)], 2);
+    assert_eq!(indices, vec![vec![0, 1]]);
+    assert!(floats_are_same(distances[0][0], 1.0));
+    assert!(floats_are_same(distances[0][1], 0.5_f64.hypot(1.0)));
+
+    let (indices, distances) = polygons::k_nearest_vertices(&tree, &[(0.5, -1.0)], 0);
+    assert_eq!(indices, vec![Vec::<usize>::new()]);
+    assert_eq!(distances, vec![Vec::<f64>::new()]);
+    let (indices, distances) = polygons::k_nearest_edges(&tree, &[(0.5, -1.0)], 0);
+    assert_eq!(indices, vec![Vec::<usize>::new()]);
+    assert_eq!(distances, vec![Vec::<f64>::new()]);
+}
+
+#[test]
+fn within_radius() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let (x_min, x_max, y_min, y_max) = get_bounds(&polygons);
+
+    let reference_points = get_random_points(200, x_min, x_max, y_min, y_max);
+    let r = 0.05 * (x_max - x_min);
+
+    let tree = polygons::build_search_tree_h(polygons.clone(), 4, 4);
+    let (offsets, indices) = polygons::vertices_within(&tree, &reference_points, r);
+    assert_eq!(offsets.len(), reference_points.len() + 1);
+
+    // points which repeat the point before them or the first point at the end
+    // of a ring are not vertices of the tree
+    let mut vertices = Vec::new();
+    let mut offset = 0;
+    for polygon in &polygons {
+        for (j, p) in polygon.iter().enumerate() {
+            let repeated = j > 0 && polygon[j - 1] == *p;
+            let closing = j > 0 && polygon[j..].iter().all(|q| *q == polygon[0]);
+            if !(repeated || closing) {
+                vertices.push((offset + j, *p));
+            }
+        }
+        offset += polygon.len();
+    }
+
+    for (i, &(rx, ry)) in reference_points.iter().enumerate() {
+        let naive: Vec<usize> = vertices
+            .iter()
+            .filter(|(_, (x, y, h))| ((x - rx).powi(2) + (y - ry).powi(2)).sqrt() + h <= r)
+            .map(|(j, _)| *j)
+            .collect();
+        assert_eq!(&indices[offsets[i]..offsets[i + 1]], &naive[..]);
+    }
+
+    let square = vec![vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]];
+    let tree = polygons::build_search_tree(square, 4, 4);
+    let (offsets, indices) =
+        polygons::edges_within(&tree, &[(0.5, -0.2), (5.0, 5.0), (1.1, 0.5)], 0.3);
+    assert_eq!(offsets, vec![0, 1, 1, 2]);
+    assert_eq!(indices, vec![0, 1]);
+}
+
+#[test]
+fn point_tree() {
+    use polygons::BulkLoading;
+
+    let mut rng = rand::rng();
+    let stations: Vec<(f64, f64, f64)> = get_random_points(500, 0.0, 10.0, 0.0, 10.0)
+        .into_iter()
+        .map(|(x, y)| (x, y, rng.random_range(0.0..2.0)))
+        .collect();
+    let reference_points = get_random_points(200, -2.0, 12.0, -2.0, 12.0);
+
+    let k = 5;
+
+    for bulk_loading in [BulkLoading::InputOrder, BulkLoading::SortTileRecursive] {
+        let tree = polygons::TreeBuilder::new()
+            .num_edges_children(8)
+            .bulk_loading(bulk_loading)
+            .build_points_h(stations.clone())
+            .unwrap();
+        assert_eq!(tree.len(), stations.len());
+
+        let (nearest, distances) = polygons::distances_nearest_points(&tree, &reference_points);
+        let (k_indices, k_distances) = polygons::k_nearest_points(&tree, &reference_points, k);
+
+        for (i, &(rx, ry)) in reference_points.iter().enumerate() {
+            let mut naive: Vec<(f64, usize)> = stations
+                .iter()
+                .enumerate()
+                .map(|(j, (x, y, h))| (((x - rx).powi(2) + (y - ry).powi(2)).sqrt() + h, j))
+                .collect();
+            naive.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            assert_eq!(nearest[i], Some(naive[0].1));
+            assert!(floats_are_same(distances[i], naive[0].0));
+
+            assert_eq!(k_indices[i].len(), k);
+            for l in 0..k {
+                assert_eq!(k_indices[i][l], naive[l].1);
+                assert!(floats_are_same(k_distances[i][l], naive[l].0));
+            }
+        }
+    }
+
+    let tree = polygons::build_point_tree(vec![(0.0, 0.0), (1.0, 0.0), (5.0, 5.0)], 2, 2);
+    let (nearest, distances) = polygons::distances_nearest_points(&tree, &[(0.9, 0.1)]);
+    assert_eq!(nearest, vec![Some(1)]);
+    assert!(floats_are_same(distances[0], 0.1_f64.hypot(0.1)));
+
+    // a large h makes the closest point lose against a farther one
+    let tree = polygons::build_point_tree_h(vec![(0.0, 0.0, 10.0), (3.0, 0.0, 0.0)], 4, 4);
+    let (nearest, _) = polygons::distances_nearest_points(&tree, &[(0.0, 0.0)]);
+    assert_eq!(nearest, vec![Some(1)]);
+
+    let tree = polygons::build_point_tree(Vec::new(), 4, 4);
+    assert!(tree.is_empty());
+    let (nearest, distances) = polygons::distances_nearest_points(&tree, &[(0.0, 0.0)]);
+    assert_eq!(nearest, vec![None]);
+    assert_eq!(distances, vec![f64::INFINITY]);
+    let (indices, _) = polygons::k_nearest_points(&tree, &[(0.0, 0.0)], 3);
+    assert_eq!(indices, vec![Vec::<usize>::new()]);
+
+    let tree = polygons::build_point_tree(vec![(0.0, 0.0), (1.0, 0.0)], 4, 4);
+    let (indices, _) = polygons::k_nearest_points(&tree, &[(0.0, 0.0)], 0);
+    assert_eq!(indices, vec![Vec::<usize>::new()]);
+
+    assert_eq!(
+        polygons::try_build_point_tree(vec![(0.0, 0.0), (f64::NAN, 1.0)], 4, 4).unwrap_err(),
+        polygons::BuildError::NonFinitePoint { point: 1 }
+    );
+    assert!(polygons::try_build_point_tree(vec![(0.0, 0.0)], 0, 4).is_err());
+}
+
+fn nearest_weighted_naive(
+    sites: &[(f64, f64, f64)],
+    p: (f64, f64),
+    weighting: &impl polygons::Weighting,
+) -> Vec<(f64, usize)> {
+    let mut naive: Vec<(f64, usize)> = sites
+        .iter()
+        .enumerate()
+        .map(|(j, (x, y, h))| {
+            let d = ((x - p.0).powi(2) + (y - p.1).powi(2)).sqrt();
+            (weighting.weight(d, *h), j)
+        })
+        .collect();
+    naive.sort_by(|a, b| a.0.total_cmp(&b.0));
+    naive
+}
+
+#[test]
+fn weighted_nearest() {
+    use polygons::{Capped, Multiplicative, Weighting};
+
+    let mut rng = rand::rng();
+    let sites: Vec<(f64, f64, f64)> = get_random_points(500, 0.0, 10.0, 0.0, 10.0)
+        .into_iter()
+        .map(|(x, y)| (x, y, rng.random_range(0.5..2.0)))
+        .collect();
+    let reference_points = get_random_points(200, -2.0, 12.0, -2.0, 12.0);
+
+    let k = 5;
+    let tree = polygons::build_point_tree_h(sites.clone(), 8, 4);
+
+    let (nearest, distances) =
+        polygons::distances_nearest_points_weighted(&tree, &reference_points, &Multiplicative);
+    let (k_indices, k_distances) =
+        polygons::k_nearest_points_weighted(&tree, &reference_points, k, &Multiplicative);
+    for (i, &p) in reference_points.iter().enumerate() {
+        let naive = nearest_weighted_naive(&sites, p, &Multiplicative);
+        assert_eq!(nearest[i], Some(naive[0].1));
+        assert!(floats_are_same(distances[i], naive[0].0));
+        for l in 0..k {
+            assert_eq!(k_indices[i][l], naive[l].1);
+            assert!(floats_are_same(k_distances[i][l], naive[l].0));
+        }
+    }
+
+    let capped = Capped { max_distance: 1.0 };
+    let (nearest, distances) =
+        polygons::distances_nearest_points_weighted(&tree, &reference_points, &capped);
+    for (i, &p) in reference_points.iter().enumerate() {
+        let naive = nearest_weighted_naive(&sites, p, &capped);
+        assert!(floats_are_same(distances[i], naive[0].0));
+        let j = nearest[i].unwrap();
+        assert!(floats_are_same(
+            capped.weight(
+                ((sites[j].0 - p.0).powi(2) + (sites[j].1 - p.1).powi(2)).sqrt(),
+                sites[j].2
+            ),
+            naive[0].0
+        ));
+    }
+
+    // vertices of a polygon tree with the same weightings
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let (x_min, x_max, y_min, y_max) = get_bounds(&polygons);
+    let reference_points = get_random_points(100, x_min, x_max, y_min, y_max);
+    let vertices: Vec<(f64, f64, f64)> = polygons.iter().flatten().copied().collect();
+
+    let tree = polygons::build_search_tree_h(polygons, 4, 4);
+    let (_, distances) =
+        polygons::distances_nearest_vertices_weighted(&tree, &reference_points, &Multiplicative);
+    let (_, k_distances) =
+        polygons::k_nearest_vertices_weighted(&tree, &reference_points, k, &Multiplicative);
+    for (i, &p) in reference_points.iter().enumerate() {
+        let naive = nearest_weighted_naive(&vertices, p, &Multiplicative);
+        assert!(floats_are_same(distances[i], naive[0].0));
+        assert!(floats_are_same(k_distances[i][0], naive[0].0));
+    }
+
+    // the additive weighting is the default
+    assert_eq!(
+        polygons::distances_nearest_vertices_weighted(
+            &tree,
+            &reference_points,
+            &polygons::Additive
+        ),
+        polygons::distances_nearest_vertices(&tree, &reference_points)
+    );
+}
+
+#[test]
+fn multipolygons() {
+    let square_with_hole = polygons::Polygon {
+        exterior: vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)],
+        interiors: vec![vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)]],
+    };
+    let two_squares = vec![
+        polygons::Polygon::from(vec![(10.0, 0.0), (11.0, 0.0), (11.0, 1.0), (10.0, 1.0)]),
+        polygons::Polygon::from(vec![(20.0, 0.0), (21.0, 0.0), (21.0, 1.0), (20.0, 1.0)]),
+    ];
+
+    let tree =
+        polygons::build_search_tree_multipolygons(vec![vec![square_with_hole], two_squares], 4, 4);
+
+    let points = vec![
+        (0.5, 2.0),
+        (2.0, 2.0),
+        (10.5, 0.5),
+        (20.5, 0.8),
+        (15.0, 0.5),
+    ];
+
+    assert_eq!(
+        polygons::points_are_inside(&tree, &points),
+        vec![true, false, true, true, false]
+    );
+    assert_eq!(
+        polygons::containing_polygons(&tree, &points),
+        vec![Some(0), None, Some(1), Some(1), None]
+    );
+
+    let distances = polygons::distances_nearest_edges_with_boundary(
+        &tree,
+        &points,
+        polygons::Boundary::Exterior,
+    );
+    assert!(floats_are_same(distances[0], 0.5));
+    assert!(floats_are_same(distances[1], 2.0));
+
+    let distances = polygons::distances_nearest_edges_with_boundary(
+        &tree,
+        &points,
+        polygons::Boundary::Interior,
+    );
+    assert!(floats_are_same(distances[0], 0.5));
+    assert!(floats_are_same(distances[1], 1.0));
+    assert!(floats_are_same(distances[2], 7.5_f64.hypot(0.5)));
+
+    // vertices are counted consecutively over all rings
+    let (indices, _) = polygons::distances_nearest_vertices(&tree, &[(1.1, 2.9), (20.9, 0.1)]);
+    assert_eq!(indices, vec![Some(7), Some(13)]);
+}
+
+#[test]
+fn fill_rules() {
+    // two overlapping squares with the same orientation
+    // and a pentagram which intersects itself
+    let polygons = vec![
+        vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+        vec![
+            (10.0, 10.0),
+            (13.0, 14.0),
+            (16.0, 10.0),
+            (11.0, 13.0),
+            (15.0, 13.0),
+        ],
+    ];
+    let tree = polygons::build_search_tree(polygons, 4, 4);
+
+    let points = vec![
+        (0.5, 0.5),
+        (1.5, 1.5),
+        (13.0, 12.5),
+        (13.0, 13.5),
+        (5.0, 5.0),
+    ];
+
+    assert_eq!(
+        polygons::points_are_inside_with_fill_rule(&tree, &points, polygons::FillRule::EvenOdd),
+        vec![true, false, false, true, false]
+    );
+    assert_eq!(
+        polygons::points_are_inside_with_fill_rule(&tree, &points, polygons::FillRule::NonZero),
+        vec![true, true, true, true, false]
+    );
+    assert_eq!(
+        polygons::points_are_inside(&tree, &points),
+        polygons::points_are_inside_with_fill_rule(&tree, &points, polygons::FillRule::EvenOdd)
+    );
+}
+
+#[test]
+fn classify_points() {
+    use polygons::Location::{Inside, OnBoundary, Outside};
+
+    let polygons = vec![
+        vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        vec![(2.0, 0.0), (3.0, 0.0), (2.0, 3.0)],
+    ];
+    let tree = polygons::build_search_tree(polygons, 4, 4);
+
+    let points = vec![
+        (0.5, 0.5),
+        (0.5, 0.0),
+        (0.0, 1.0),
+        (1.0, 0.3),
+        (2.5, 1.5),
+        (2.0, 1.0),
+        (1.5, 0.5),
+        (0.5, 1.001),
+    ];
+
+    assert_eq!(
+        polygons::classify_points(&tree, &points, 0.0),
+        vec![
+            Inside, OnBoundary, OnBoundary, OnBoundary, OnBoundary, OnBoundary, Outside, Outside
+        ]
+    );
+    assert_eq!(
+        polygons::classify_points(&tree, &points, 0.01)[7],
+        OnBoundary
+    );
+
+    // away from the boundary the classification agrees with points_are_inside
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let tree = polygons::build_search_tree_h(zero_out_h(polygons), 4, 4);
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+    let contains = polygons::points_are_inside(&tree, &reference_points);
+    let locations = polygons::classify_points(&tree, &reference_points, 0.0);
+    for (&x, &location) in contains.iter().zip(locations.iter()) {
+        assert_eq!(location, if x { Inside } else { Outside });
+    }
+}
+
+#[test]
+fn robust_precision() {
+    for (case, polygons_file) in [
+        ("case-1", "tests/case-1/islands.txt"),
+        ("case-2", "tests/case-2/boundary.txt"),
+    ] {
+        let polygons = zero_out_h(read_polygons(polygons_file));
+        let mut tree = polygons::build_search_tree_h(polygons, 4, 4);
+        tree.set_precision(polygons::Precision::Robust);
+
+        let reference_points =
+            read_tuples(&format!("tests/{}/reference/reference_points.txt", case));
+        let contains = polygons::points_are_inside(&tree, &reference_points);
+        let reference_bools: Vec<bool> =
+            read_vector(&format!("tests/{}/reference/points_are_inside.txt", case));
+        assert_eq!(contains, reference_bools);
+    }
+
+    // the edge from (-12, -12) to (24, 24) lies on the diagonal and the points
+    // are so close to it that the cross product in plain floating point
+    // arithmetic gets the side wrong for some of them
+    let triangle = vec![vec![(-12.0, -12.0), (24.0, 24.0), (-12.0, 24.0)]];
+    let mut tree = polygons::build_search_tree(triangle, 4, 4);
+    tree.set_precision(polygons::Precision::Robust);
+
+    let ulp = 0.5_f64.next_up() - 0.5;
+    let mut points = Vec::new();
+    for i in 0..64 {
+        for j in 0..64 {
+            points.push((0.5 + i as f64 * ulp, 0.5 + j as f64 * ulp));
+        }
+    }
+
+    let locations = polygons::classify_points(&tree, &points, 0.0);
+    for (&(x, y), &location) in points.iter().zip(locations.iter()) {
+        let expected = if y > x {
+            polygons::Location::Inside
+        } else if y < x {
+            polygons::Location::Outside
+        } else {
+            polygons::Location::OnBoundary
+        };
+        assert_eq!(location, expected);
+    }
+}
+
+#[test]
+fn serialize() {
+    let polygons = read_polygons("tests/case-1/islands.txt");
+    let mut tree = polygons::build_search_tree_h(polygons, 4, 4);
+    tree.set_precision(polygons::Precision::Robust);
+
+    let bytes = tree.to_bytes();
+    let loaded = polygons::Tree::from_bytes(&bytes).unwrap();
+    assert_eq!(loaded.precision(), polygons::Precision::Robust);
+    assert_eq!(loaded.to_bytes(), bytes);
+
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+    assert_eq!(
+        polygons::points_are_inside(&loaded, &reference_points),
+        polygons::points_are_inside(&tree, &reference_points)
+    );
+    assert_eq!(
+        polygons::distances_nearest_vertices(&loaded, &reference_points),
+        polygons::distances_nearest_vertices(&tree, &reference_points)
+    );
+    assert_eq!(
+        polygons::distances_nearest_edges(&loaded, &reference_points),
+        polygons::distances_nearest_edges(&tree, &reference_points)
+    );
+
+    let empty = polygons::Tree::from_bytes(&polygons::Tree::default().to_bytes()).unwrap();
+    assert!(empty.is_empty());
+
+    use polygons::LoadError;
+    assert_eq!(
+        polygons::Tree::from_bytes(b"not a tree").unwrap_err(),
+        LoadError::InvalidMagic
+    );
+
+    let mut newer = bytes.clone();
+    newer[8] = 99;
+    assert_eq!(
+        polygons::Tree::from_bytes(&newer).unwrap_err(),
+        LoadError::UnsupportedVersion(99)
+    );
+
+    assert_eq!(
+        polygons::Tree::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        LoadError::UnexpectedEnd
+    );
+
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(
+        polygons::Tree::from_bytes(&longer).unwrap_err(),
+        LoadError::TrailingBytes
+    );
+
+    // xmin of the root node becomes NaN
+    let mut corrupt = bytes.clone();
+    corrupt[72..80].copy_from_slice(&f64::NAN.to_le_bytes());
+    assert!(matches!(
+        polygons::Tree::from_bytes(&corrupt).unwrap_err(),
+        LoadError::Invalid(_)
+    ));
+
+    // absurdly large number of nodes
+    let mut corrupt = bytes.clone();
+    corrupt[56..64].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(
+        polygons::Tree::from_bytes(&corrupt).unwrap_err(),
+        LoadError::UnexpectedEnd
+    );
+
+    // the children of the root node point back to the root node
+    let mut corrupt = bytes.clone();
+    corrupt[112..120].copy_from_slice(&0_u64.to_le_bytes());
+    assert!(matches!(
+        polygons::Tree::from_bytes(&corrupt).unwrap_err(),
+        LoadError::Invalid(_)
+    ));
+}
+
+#[test]
+fn bulk_loading() {
+    use polygons::BulkLoading;
+
+    // the polygons in a random order so that the input order is not spatially coherent
+    let mut polygons = zero_out_h(read_polygons("tests/case-1/islands.txt"));
+    let mut rng = rand::rng();
+    for i in (1..polygons.len()).rev() {
+        polygons.swap(i, rng.random_range(0..=i));
+    }
+
+    let reference_points = read_tuples("tests/case-1/reference/reference_points.txt");
+    let reference_edges: Vec<f64> =
+        read_vector("tests/case-1/reference/distances_nearest_edges.txt");
+    let reference_vertices: Vec<f64> =
+        read_vector("tests/case-1/reference/distances_nearest_vertices.txt");
+    let reference_bools: Vec<bool> = read_vector("tests/case-1/reference/points_are_inside.txt");
+
+    for bulk_loading in [
+        BulkLoading::InputOrder,
+        BulkLoading::SortTileRecursive,
+        BulkLoading::Hilbert,
+        BulkLoading::Morton,
+    ] {
+        for num_nodes_children in [2, 16] {
+            let tree = polygons::TreeBuilder::new()
+                .num_edges_children(4)
+                .num_nodes_children(num_nodes_children)
+                .bulk_loading(bulk_loading)
+                .build_h(polygons.clone())
+                .unwrap();
+
+            // loading checks that the children of every node lie within its box
+            assert!(polygons::Tree::from_bytes(&tree.to_bytes()).is_ok());
+
+            let distances = polygons::distances_nearest_edges(&tree, &reference_points);
+            for (&x, &rx) in distances.iter().zip(reference_edges.iter()) {
+                assert!(floats_are_same(x, rx));
+            }
+
+            let (_, distances) = polygons::distances_nearest_vertices(&tree, &reference_points);
+            for (&x, &rx) in distances.iter().zip(reference_vertices.iter()) {
+                assert!(floats_are_same(x, rx));
+            }
+
+            let contains = polygons::points_are_inside(&tree, &reference_points);
+            assert_eq!(contains, reference_bools);
+        }
+    }
+}
+
+#[test]
+fn tree_builder() {
+    use polygons::{BuildError, FillRule, Precision, QueryError, TreeBuilder};
+
+    // two overlapping squares with the same orientation
+    let polygons = vec![
+        vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        vec![(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+    ];
+    let points = vec![(0.5, 0.5), (1.5, 1.5), (2.05, 0.5), (5.0, 5.0)];
+
+    let tree = TreeBuilder::new().build(polygons.clone()).unwrap();
+    assert_eq!(tree.fill_rule(), FillRule::EvenOdd);
+    assert_eq!(
+        polygons::points_are_inside(&tree, &points),
+        vec![true, false, false, false]
+    );
+
+    let tree = TreeBuilder::new()
+        .num_edges_children(2)
+        .num_nodes_children(2)
+        .fill_rule(FillRule::NonZero)
+        .tolerance(0.1)
+        .precision(Precision::Robust)
+        .build(polygons.clone())
+        .unwrap();
+    assert_eq!(tree.fill_rule(), FillRule::NonZero);
+    assert_eq!(tree.tolerance(), 0.1);
+    assert_eq!(tree.precision(), Precision::Robust);
+    assert_eq!(
+        polygons::points_are_inside(&tree, &points),
+        vec![true, true, true, false]
+    );
+
+    // the options survive a round trip through bytes
+    let loaded = polygons::Tree::from_bytes(&tree.to_bytes()).unwrap();
+    assert_eq!(loaded.fill_rule(), FillRule::NonZero);
+    assert_eq!(loaded.tolerance(), 0.1);
+    assert_eq!(loaded.precision(), Precision::Robust);
+    assert!(loaded.has_polygon_ids());
+
+    assert_eq!(
+        TreeBuilder::new()
+            .tolerance(-1.0)
+            .build(polygons.clone())
+            .unwrap_err(),
+        BuildError::InvalidTolerance { value: -1.0 }
+    );
+
+    let tree = TreeBuilder::new()
+        .store_polygon_ids(false)
+        .build(polygons.clone())
+        .unwrap();
+    assert!(!tree.has_polygon_ids());
+    assert_eq!(
+        polygons::try_containing_polygons(&tree, &points).unwrap_err(),
+        QueryError::MissingPolygonIds
+    );
+    assert_eq!(
+        polygons::try_all_containing_polygons(&tree, &points).unwrap_err(),
+        QueryError::MissingPolygonIds
+    );
+    assert_eq!(
+        polygons::try_nearest_edges(&tree, &points).unwrap_err(),
+        QueryError::MissingPolygonIds
+    );
+
+    // queries which do not need the ids still work
+    let reference = polygons::build_search_tree(polygons, 4, 4);
+    assert_eq!(
+        polygons::distances_nearest_edges(&tree, &points),
+        polygons::distances_nearest_edges(&reference, &points)
+    );
+    let loaded = polygons::Tree::from_bytes(&tree.to_bytes()).unwrap();
+    assert!(!loaded.has_polygon_ids());
+}
+
+#[test]
+fn insert_and_remove_polygons() {
+    use polygons::{BuildError, BulkLoading, QueryError, TreeBuilder};
+
+    let polygons = zero_out_h(read_polygons("tests/case-1/islands.txt"));
+    let points = read_tuples("tests/case-1/reference/reference_points.txt");
+    let half = polygons.len() / 2;
+
+    // rebalancing after the insertions keeps the bulk loading of the build
+    for (num_nodes_children, bulk_loading) in [
+        (2, BulkLoading::InputOrder),
+        (2, BulkLoading::Hilbert),
+        (4, BulkLoading::Morton),
+        (16, BulkLoading::SortTileRecursive),
+    ] {
+        let builder = TreeBuilder::new()
+            .num_nodes_children(num_nodes_children)
+            .bulk_loading(bulk_loading);
+
+        // half of the polygons are built and the other half inserted one by one
+        // which gives the same ids and vertex indices as building all at once
+        let mut tree = builder.build_h(polygons[..half].to_vec()).unwrap();
+        for (i, polygon) in polygons[half..].iter().enumerate() {
+            assert_eq!(tree.insert_polygon_h(polygon.clone()).unwrap(), half + i);
+        }
+        let full = builder.build_h(polygons.clone()).unwrap();
+        assert_eq!(tree.num_polygons(), full.num_polygons());
+        assert_eq!(tree.num_vertices(), full.num_vertices());
+
+        assert_eq!(
+            polygons::points_are_inside(&tree, &points),
+            polygons::points_are_inside(&full, &points)
+        );
+        assert_eq!(
+            polygons::containing_polygons(&tree, &points),
+            polygons::containing_polygons(&full, &points)
+        );
+        assert_eq!(
+            polygons::distances_nearest_edges(&tree, &points),
+            polygons::distances_nearest_edges(&full, &points)
+        );
+        assert_eq!(
+            polygons::distances_nearest_vertices(&tree, &points),
+            polygons::distances_nearest_vertices(&full, &points)
+        );
+
+        // every third polygon is removed and the result is compared to a tree
+        // built from the remaining polygons, whose ids and indices are shifted
+        let mut kept = Vec::new();
+        let mut vertex_indices = Vec::new();
+        let mut offset = 0;
+        for (i, polygon) in polygons.iter().enumerate() {
+            if i % 3 == 0 {
+                tree.remove_polygon(i).unwrap();
+            } else {
+                kept.push(i);
+                vertex_indices.extend(offset..offset + polygon.len());
+            }
+            offset += polygon.len();
+        }
+        let remaining: Vec<_> = kept.iter().map(|&i| polygons[i].clone()).collect();
+        let reference = builder.build_h(remaining).unwrap();
+
+        for loaded in [
+            tree.clone(),
+            polygons::Tree::from_bytes(&tree.to_bytes()).unwrap(),
+        ] {
+            assert_eq!(
+                polygons::containing_polygons(&loaded, &points),
+                polygons::containing_polygons(&reference, &points)
+                    .into_iter()
+                    .map(|polygon| polygon.map(|k| kept[k]))
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                polygons::distances_nearest_edges(&loaded, &points),
+                polygons::distances_nearest_edges(&reference, &points)
+            );
+            let (indices, distances) = polygons::distances_nearest_vertices(&reference, &points);
+            assert_eq!(
+                polygons::distances_nearest_vertices(&loaded, &points),
+                (
+                    indices
+                        .into_iter()
+                        .map(|index| index.map(|v| vertex_indices[v]))
+                        .collect(),
+                    distances
+                )
+            );
+        }
+
+        assert_eq!(
+            tree.remove_polygon(0).unwrap_err(),
+            QueryError::UnknownPolygon { polygon: 0 }
+        );
+        assert_eq!(
+            tree.remove_polygon(polygons.len()).unwrap_err(),
+            QueryError::UnknownPolygon {
+                polygon: polygons.len()
+            }
+        );
+
+        for &i in &kept {
+            tree.remove_polygon(i).unwrap();
+        }
+        assert!(tree.is_empty());
+        assert_eq!(
+            polygons::points_are_inside(&tree, &points),
+            vec![false; points.len()]
+        );
+    }
+
+    // ids are not reused and a failed insertion does not use up an id
+    let mut tree =
+        polygons::build_search_tree(vec![vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]], 4, 4);
+    tree.remove_polygon(0).unwrap();
+    assert_eq!(
+        tree.insert_polygon(vec![(0.0, 0.0), (1.0, 0.0)])
+            .unwrap_err(),
+        BuildError::DegeneratePolygon {
+            polygon: 1,
+            ring: 0,
+            num_points: 2
+        }
+    );
+    // the tree is empty here, an empty multipolygon is refused all the same
+    assert_eq!(
+        tree.insert_multipolygon(Vec::new()).unwrap_err(),
+        BuildError::EmptyPolygon { polygon: 1 }
+    );
+    let square = polygons::Polygon {
+        exterior: vec![(0.0, 0.0), (3.0, 0.0), (3.0, 3.0), (0.0, 3.0)],
+        interiors: vec![vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0)]],
+    };
+    assert_eq!(tree.insert_multipolygon(vec![square]).unwrap(), 1);
+    assert_eq!(
+        tree.insert_multipolygon(Vec::new()).unwrap_err(),
+        BuildError::EmptyPolygon { polygon: 2 }
+    );
+    assert_eq!(tree.num_polygons(), 2);
+    assert_eq!(tree.num_vertices(), 3 + 8);
+    assert_eq!(
+        polygons::containing_polygons(&tree, &[(0.5, 0.5), (1.5, 1.5)]),
+        vec![Some(1), None]
+    );
+    assert_eq!(
+        polygons::distances_nearest_vertices(&tree, &[(3.1, 3.0)]).0,
+        vec![Some(5)]
+    );
+
+    let mut tree = TreeBuilder::new()
+        .store_polygon_ids(false)
+        .build_h(polygons.clone())
+        .unwrap();
+    assert_eq!(
+        tree.remove_polygon(0).unwrap_err(),
+        QueryError::MissingPolygonIds
+    );
+    tree.insert_polygon(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)])
+        .unwrap();
+    assert!(polygons::Tree::from_bytes(&tree.to_bytes()).is_ok());
+}
+
 #[ignore]
 #[test]
 fn benchmark() {
@@ -234,6 +2038,7 @@ fn benchmark() {
     let start = Instant::now();
     let tree = polygons::build_search_tree_h(polygons, 16, 16);
     println!("time elapsed in building tree: {:?}", start.elapsed());
+    println!("memory used by tree: {} bytes", tree.memory_usage());
 
     let start = Instant::now();
     let _distances = polygons::distances_nearest_edges(&tree, &reference_points);
@@ -252,4 +2057,42 @@ fn benchmark() {
     let start = Instant::now();
     let _contains = polygons::points_are_inside(&tree, &reference_points);
     println!("time elapsed in points_are_inside: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let _distances = polygons::signed_distances(&tree, &reference_points);
+    println!("time elapsed in signed_distances: {:?}", start.elapsed());
+
+    // the same polygons in random order for each bulk loading strategy
+    let mut polygons = zero_out_h(read_polygons("tests/case-1/islands.txt"));
+    let mut rng = rand::rng();
+    for i in (1..polygons.len()).rev() {
+        polygons.swap(i, rng.random_range(0..=i));
+    }
+    for bulk_loading in [
+        polygons::BulkLoading::InputOrder,
+        polygons::BulkLoading::SortTileRecursive,
+        polygons::BulkLoading::Hilbert,
+        polygons::BulkLoading::Morton,
+    ] {
+        let start = Instant::now();
+        let tree = polygons::TreeBuilder::new()
+            .num_edges_children(16)
+            .num_nodes_children(16)
+            .bulk_loading(bulk_loading)
+            .build_h(polygons.clone())
+            .unwrap();
+        println!(
+            "time elapsed in building tree with {:?}: {:?}",
+            bulk_loading,
+            start.elapsed()
+        );
+
+        let start = Instant::now();
+        let _distances = polygons::distances_nearest_edges(&tree, &reference_points);
+        println!(
+            "time elapsed in distances_nearest_edges with {:?}: {:?}",
+            bulk_loading,
+            start.elapsed()
+        );
+    }
 }
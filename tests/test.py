@@ -1,6 +1,7 @@
 import polygons
 import os
 import sys
+import pytest
 
 
 def floats_are_same(f1, f2):
@@ -81,3 +82,375 @@ def test_interface():
         lambda x: float(x),
     )
     assert all([floats_are_same(a, b) for a, b in zip(distances, distances_reference)])
+
+
+def test_build_errors():
+    with pytest.raises(ValueError):
+        polygons.build_search_tree([[(0.0, 0.0), (1.0, 0.0)]], 4, 4)
+
+    with pytest.raises(ValueError):
+        polygons.build_search_tree([[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]], 0, 4)
+
+
+def test_empty_tree():
+    tree = polygons.build_search_tree([], 4, 4)
+    points = [(0.5, 0.5), (2.0, -1.0)]
+
+    assert polygons.points_are_inside(tree, points) == [False, False]
+    assert polygons.distances_nearest_edges(tree, points) == [float("inf")] * 2
+
+    indices, distances = polygons.distances_nearest_vertices(tree, points)
+    assert indices == [None, None]
+    assert distances == [float("inf")] * 2
+
+
+def test_containing_polygons():
+    ps = [
+        [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        [(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+    ]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    points = [(0.5, 0.5), (1.5, 1.5), (4.0, 4.0)]
+
+    assert polygons.containing_polygons(tree, points) == [0, 0, None]
+    assert polygons.all_containing_polygons(tree, points) == [[0], [0, 1], []]
+
+
+def test_nearest_edges():
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+
+    (edge,) = polygons.nearest_edges(tree, [(0.25, -0.5)])
+    assert edge.polygon == 0
+    assert edge.index == 0
+    assert floats_are_same(edge.t, 0.25)
+    assert points_are_same((edge.x, edge.y), (0.25, 0.0))
+    assert floats_are_same(edge.distance, 0.5)
+
+
+def test_signed_distances():
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+
+    distances = polygons.signed_distances(tree, [(0.5, 0.25), (0.5, -0.5)])
+    assert all([floats_are_same(a, b) for a, b in zip(distances, [-0.25, 0.5])])
+
+
+def test_k_nearest():
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+
+    indices, distances = polygons.k_nearest_vertices(tree, [(0.25, -1.0)], 2)
+    assert indices == [[0, 1]]
+    assert floats_are_same(distances[0][0], (0.25**2 + 1.0) ** 0.5)
+
+    indices, _ = polygons.k_nearest_edges(tree, [(0.5, -1.0)], 2)
+    assert indices == [[0, 1]]
+
+
+def test_within():
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    points = [(0.5, -0.5), (5.0, 5.0), (1.1, 0.5)]
+
+    offsets, indices = polygons.vertices_within(tree, points, 0.75)
+    assert offsets == [0, 2, 2, 4]
+    assert indices == [0, 1, 1, 2]
+
+    points = [(0.5, -0.2), (5.0, 5.0), (1.1, 0.5)]
+    offsets, indices = polygons.edges_within(tree, points, 0.3)
+    assert offsets == [0, 1, 1, 2]
+    assert indices == [0, 1]
+
+
+def test_multipolygons():
+    square_with_hole = [
+        [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)],
+        [(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+    ]
+    square = [[(10.0, 0.0), (11.0, 0.0), (11.0, 1.0), (10.0, 1.0)]]
+    tree = polygons.build_search_tree_multipolygons([[square_with_hole], [square]], 4, 4)
+    points = [(0.5, 2.0), (2.0, 2.0), (10.5, 0.5)]
+
+    assert polygons.containing_polygons(tree, points) == [0, None, 1]
+
+    distances = polygons.distances_nearest_edges_with_boundary(
+        tree, points, polygons.Boundary.Interior
+    )
+    assert floats_are_same(distances[0], 0.5)
+    assert floats_are_same(distances[1], 1.0)
+
+
+def test_fill_rules():
+    ps = [
+        [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        [(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+    ]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    points = [(0.5, 0.5), (1.5, 1.5)]
+
+    assert polygons.points_are_inside(tree, points) == [True, False]
+    assert polygons.points_are_inside(
+        tree, points, fill_rule=polygons.FillRule.NonZero
+    ) == [True, True]
+
+
+def test_classify_points():
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    points = [(0.5, 0.5), (0.5, 0.0), (2.0, 2.0)]
+
+    assert polygons.classify_points(tree, points) == [
+        polygons.Location.Inside,
+        polygons.Location.OnBoundary,
+        polygons.Location.Outside,
+    ]
+
+
+def test_precision():
+    ps = [[(-12.0, -12.0), (24.0, 24.0), (-12.0, 24.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    assert tree.precision == polygons.Precision.Fast
+
+    tree.precision = polygons.Precision.Robust
+    assert tree.precision == polygons.Precision.Robust
+
+    ulp = 1.1102230246251565e-16
+    points = [
+        (0.5 + i * ulp, 0.5 + j * ulp) for i in range(16) for j in range(16) if i != j
+    ]
+    inside = polygons.points_are_inside(tree, points)
+    assert inside == [y > x for (x, y) in points]
+
+
+def test_serialize():
+    import pickle
+
+    ps = [[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]]
+    tree = polygons.build_search_tree(ps, 4, 4)
+    tree.precision = polygons.Precision.Robust
+
+    points = [(0.5, 0.5), (2.0, 0.5), (0.5, -1.0)]
+
+    for loaded in [
+        polygons.Tree.from_bytes(tree.to_bytes()),
+        pickle.loads(pickle.dumps(tree)),
+    ]:
+        assert loaded.precision == polygons.Precision.Robust
+        assert polygons.points_are_inside(loaded, points) == [True, False, False]
+        assert polygons.distances_nearest_edges(
+            loaded, points
+        ) == polygons.distances_nearest_edges(tree, points)
+
+    with pytest.raises(ValueError):
+        polygons.Tree.from_bytes(b"not a tree")
+
+    with pytest.raises(ValueError):
+        polygons.Tree.from_bytes(tree.to_bytes()[:-1])
+
+
+def test_build_options():
+    # two overlapping squares with the same orientation
+    ps = [
+        [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)],
+        [(1.0, 1.0), (3.0, 1.0), (3.0, 3.0), (1.0, 3.0)],
+    ]
+    points = [(0.5, 0.5), (1.5, 1.5), (2.05, 0.5), (5.0, 5.0)]
+
+    tree = polygons.build_search_tree(
+        ps,
+        2,
+        2,
+        bulk_loading=polygons.BulkLoading.SortTileRecursive,
+        fill_rule=polygons.FillRule.NonZero,
+        tolerance=0.1,
+        precision=polygons.Precision.Robust,
+    )
+    assert tree.fill_rule == polygons.FillRule.NonZero
+    assert tree.tolerance == 0.1
+    assert tree.precision == polygons.Precision.Robust
+    assert polygons.points_are_inside(tree, points) == [True, True, True, False]
+    assert polygons.points_are_inside(
+        tree, points, fill_rule=polygons.FillRule.EvenOdd
+    ) == [True, False, True, False]
+
+    with pytest.raises(ValueError):
+        polygons.build_search_tree(ps, tolerance=-1.0)
+
+    tree = polygons.build_search_tree(ps, store_polygon_ids=False)
+    assert not tree.has_polygon_ids
+    with pytest.raises(ValueError):
+        polygons.containing_polygons(tree, points)
+    with pytest.raises(ValueError):
+        polygons.nearest_edges(tree, points)
+
+
+def test_insert_and_remove():
+    ps = [
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        [(2.0, 0.0), (3.0, 0.0), (3.0, 1.0), (2.0, 1.0)],
+    ]
+    points = [(0.5, 0.5), (2.5, 0.5), (4.8, 0.3)]
+
+    tree = polygons.build_search_tree(ps[:1])
+    assert tree.insert_polygon(ps[1]) == 1
+    assert tree.insert_polygon([(4.0, 0.0), (5.0, 0.0), (5.0, 1.0)]) == 2
+    assert tree.num_polygons == 3
+    assert tree.num_vertices == 11
+    assert polygons.containing_polygons(tree, points) == [0, 1, 2]
+
+    tree.remove_polygon(1)
+    assert polygons.containing_polygons(tree, points) == [0, None, 2]
+
+    with pytest.raises(ValueError):
+        tree.remove_polygon(1)
+
+    with pytest.raises(ValueError):
+        tree.insert_polygon([(0.0, 0.0), (1.0, 0.0)])
+
+    loaded = polygons.Tree.from_bytes(tree.to_bytes())
+    assert loaded.insert_polygon(ps[1]) == 3
+    assert polygons.containing_polygons(loaded, points) == [0, 3, 2]
+
+
+def test_closed_rings():
+    square = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]
+    tree = polygons.build_search_tree([square + [square[0]], square])
+    points = [(0.1, 2.1), (1.0, 1.0)]
+
+    # indices refer to the lists as given, so the second square starts at 5
+    indices, _ = polygons.distances_nearest_vertices(tree, points[:1])
+    assert indices == [3]
+    assert polygons.points_are_inside(tree, points) == [False, False]
+    assert polygons.containing_polygons(tree, points) == [None, 0]
+    assert tree.num_vertices == 9
+
+
+def test_polylines():
+    river = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0)]
+    points = [(1.0, 1.0), (-1.0, 0.0)]
+
+    tree = polygons.build_search_tree_polylines([river])
+    assert tree.has_polylines
+    assert polygons.distances_nearest_edges(tree, points) == [1.0, 1.0]
+
+    with pytest.raises(ValueError):
+        polygons.points_are_inside(tree, points)
+
+    with pytest.raises(ValueError):
+        polygons.signed_distances(tree, points)
+
+    square = [(3.0, 0.0), (4.0, 0.0), (4.0, 1.0), (3.0, 1.0)]
+    tree = polygons.build_search_tree([square], polylines=[river])
+    nearest = polygons.nearest_edges(tree, [(1.0, 0.5), (3.5, 1.5)])
+    assert [edge.polygon for edge in nearest] == [1, 0]
+
+
+def test_point_tree():
+    stations = [(0.0, 0.0), (1.0, 0.0), (5.0, 5.0), (9.0, 1.0)]
+    tree = polygons.build_point_tree(stations, num_points_children=2)
+    assert len(tree) == 4
+
+    indices, distances = polygons.distances_nearest_points(tree, [(0.9, 0.1), (8.0, 1.0)])
+    assert indices == [1, 3]
+    assert floats_are_same(distances[1], 1.0)
+
+    indices, distances = polygons.k_nearest_points(tree, [(0.0, 0.1)], 2)
+    assert indices == [[0, 1]]
+    assert distances[0][0] <= distances[0][1]
+
+    # h is added to the distance so a nearby point with large h loses
+    tree = polygons.build_point_tree_h(
+        [(0.0, 0.0, 10.0), (3.0, 0.0, 0.0)],
+        bulk_loading=polygons.BulkLoading.SortTileRecursive,
+    )
+    indices, distances = polygons.distances_nearest_points(tree, [(0.0, 0.0)])
+    assert indices == [1]
+    assert floats_are_same(distances[0], 3.0)
+
+    with pytest.raises(ValueError):
+        polygons.build_point_tree([(0.0, float("nan"))])
+
+
+def test_distances_nearest_edges_h():
+    square = [[(0.0, 0.0, 0.0), (4.0, 0.0, 4.0), (4.0, 4.0, 4.0), (0.0, 4.0, 4.0)]]
+    tree = polygons.build_search_tree_h(square)
+
+    # h changes faster along the bottom edge than the distance so its start is nearest
+    distances = polygons.distances_nearest_edges_h(tree, [(1.0, -1.0), (2.0, 2.0)])
+    assert floats_are_same(distances[0], 2.0**0.5)
+    assert floats_are_same(distances[1], 2.0 * 2.0**0.5)
+
+
+def test_weighting():
+    sites = [(0.0, 0.0, 4.0), (3.0, 0.0, 1.0)]
+    tree = polygons.build_point_tree_h(sites)
+
+    # additive: 1 + 4 against 2 + 1
+    indices, distances = polygons.distances_nearest_points(tree, [(1.0, 0.0)])
+    assert indices == [1]
+    assert floats_are_same(distances[0], 3.0)
+
+    # multiplicative: 1 * 4 against 2 * 1
+    indices, distances = polygons.distances_nearest_points(
+        tree, [(1.0, 0.0)], weighting=polygons.Weighting.Multiplicative
+    )
+    assert indices == [1]
+    assert floats_are_same(distances[0], 2.0)
+
+    # capped: min(1, 0.5) + 4 against min(2, 0.5) + 1
+    indices, distances = polygons.k_nearest_points(
+        tree,
+        [(1.0, 0.0)],
+        2,
+        weighting=polygons.Weighting.Capped,
+        max_distance=0.5,
+    )
+    assert indices == [[1, 0]]
+    assert floats_are_same(distances[0][0], 1.5)
+    assert floats_are_same(distances[0][1], 4.5)
+
+    # only the capped weighting takes a max_distance and it needs one
+    with pytest.raises(ValueError):
+        polygons.distances_nearest_points(
+            tree, [(1.0, 0.0)], weighting=polygons.Weighting.Capped
+        )
+    with pytest.raises(ValueError):
+        polygons.distances_nearest_points(tree, [(1.0, 0.0)], max_distance=0.5)
+
+    square = [[(0.0, 0.0, 1.0), (1.0, 0.0, 2.0), (1.0, 1.0, 3.0), (0.0, 1.0, 4.0)]]
+    tree = polygons.build_search_tree_h(square)
+    indices, distances = polygons.k_nearest_vertices(
+        tree, [(3.0, 0.0)], 2, weighting=polygons.Weighting.Multiplicative
+    )
+    assert indices == [[0, 1]]
+    assert floats_are_same(distances[0][0], 3.0)
+    assert floats_are_same(distances[0][1], 4.0)
+
+
+def test_payloads():
+    import pickle
+
+    ps = [
+        [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)],
+        [(2.0, 0.0), (3.0, 0.0), (3.0, 1.0), (2.0, 1.0)],
+    ]
+    tree = polygons.build_search_tree(ps, payloads=[{"name": "left"}, 17])
+
+    points = [(0.5, 0.5), (2.5, 0.5), (5.0, 5.0)]
+    assert polygons.containing_payloads(tree, points) == [{"name": "left"}, 17, None]
+
+    indices, _ = polygons.distances_nearest_vertices(tree, [(3.2, 0.0)])
+    assert tree.vertex_polygon(indices[0]) == 1
+    assert tree.vertex_payload(indices[0]) == 17
+
+    assert tree.set_payload(1, "right") == 17
+    assert tree.payload(1) == "right"
+    with pytest.raises(ValueError):
+        tree.set_payload(5, "missing")
+
+    loaded = pickle.loads(pickle.dumps(tree))
+    assert polygons.containing_payloads(loaded, points) == [{"name": "left"}, "right", None]
+
+    loaded = polygons.Tree.from_bytes(tree.to_bytes())
+    assert loaded.payload(0) is None